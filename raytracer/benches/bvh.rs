@@ -0,0 +1,78 @@
+// tree vs linear bvh on the two mesh-heavy scenes, run with
+//   cargo bench --bench bvh
+// every layout traces the same camera rays plus one random bounce per hit,
+// the best of a few rounds is reported
+
+use raytracer::hittable::bvh::BvhLayout;
+use raytracer::hittable::Hittable;
+use raytracer::util::interval::Interval;
+use raytracer::util::random;
+use raytracer::util::ray::Ray;
+use raytracer::util::vec3::random_unit_vector;
+use raytracer::util::RenderParam;
+use raytracer::worlds::scene_by_name;
+use std::time::{Duration, Instant};
+
+const SCENES: [&str; 2] = ["final_scene", "_obj_test"];
+const WIDTH: u32 = 400;
+const ROUNDS: usize = 5;
+
+fn main() {
+    for name in SCENES {
+        let mut base = None;
+        for layout in [BvhLayout::Tree, BvhLayout::Linear] {
+            let param = RenderParam {
+                image_width: WIDTH,
+                samples_per_pixel: 1,
+                seed: 1,
+                bvh_layout: layout,
+                ..Default::default()
+            };
+            random::seed(param.seed);
+            let (world, _, cam, _) = scene_by_name(name).unwrap()(&param);
+
+            let mut best = Duration::MAX;
+            let mut rays = 0;
+            for _ in 0..ROUNDS {
+                random::seed(param.seed);
+                let start = Instant::now();
+                rays = trace(&world, |i, j| cam.get_ray(i, j, 0, 0), cam.image_height());
+                best = best.min(start.elapsed());
+            }
+
+            let secs = best.as_secs_f64();
+            let speedup = match base {
+                Some(base) => format!(", {:.2}x", base / secs),
+                None => String::new(),
+            };
+            base.get_or_insert(secs);
+            println!(
+                "{:<12} {:<7} {:>8} rays in {:>8.2}ms, {:>6.2} Mrays/s{}",
+                name,
+                layout.to_string(),
+                rays,
+                secs * 1000.0,
+                rays as f64 / secs / 1e6,
+                speedup
+            );
+        }
+    }
+}
+
+fn trace(world: &dyn Hittable, get_ray: impl Fn(u32, u32) -> Ray, height: u32) -> usize {
+    let mut rays = 0;
+    for j in 0..height {
+        for i in 0..WIDTH {
+            let r = get_ray(i, j);
+            let mut rec = None;
+            rays += 1;
+            if world.hit(&r, &Interval::new(0.001, f64::INFINITY), &mut rec) {
+                let rec = rec.unwrap();
+                let bounce = Ray::new(rec.p, rec.normal + random_unit_vector(), r.time());
+                world.hit(&bounce, &Interval::new(0.001, f64::INFINITY), &mut None);
+                rays += 1;
+            }
+        }
+    }
+    rays
+}
@@ -1,8 +1,8 @@
-use rand::rngs::ThreadRng;
 use rand::Rng;
 
+use crate::util::random::{rng, LocalRng};
 use crate::util::ray::Ray;
-use crate::util::{degrees_to_radians, vec3::*, IMAGE_HEIGHT};
+use crate::util::{degrees_to_radians, vec3::*};
 
 pub mod background;
 
@@ -34,6 +34,10 @@ pub struct Camera {
     pub defocus_angle: f64, // Variation angle of rays through each pixel
     pub focus_dist: f64,    // Distance from lookfrom to focus plane
 
+    // rays are sent at times in between, for motion blur
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+
     pub gauss_fuzzing_scale: f64, // do not fuzz if 0.0
     pub edge_detect: bool,
 }
@@ -51,6 +55,8 @@ impl Default for Camera {
             vup: Vec3::new(0.0, 1.0, 0.0),
             defocus_angle: 0.0,
             focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
             image_height: 1,
             center: Point3::default(),
             pixel00_loc: Point3::default(),
@@ -72,7 +78,7 @@ impl Default for Camera {
 
 impl Camera {
     pub fn initialize(&mut self) {
-        self.image_height = IMAGE_HEIGHT;
+        self.image_height = (self.image_width as f64 / self.aspect_ratio) as u32;
         self.image_height = if self.image_height < 1 {
             1
         } else {
@@ -113,7 +119,7 @@ impl Camera {
         self.defocus_disk_v = self.v * defocus_radius;
     }
     pub fn get_ray(&self, i: u32, j: u32, s_i: u32, s_j: u32) -> Ray {
-        let mut rng = rand::thread_rng();
+        let mut rng = rng();
         // new changes: we stratify one pixel, and make the samples
         // distribute more averagely
         let offset = self.sample_square_stratified(s_i, s_j, &mut rng);
@@ -126,16 +132,24 @@ impl Camera {
             self.defocus_disk_sample()
         };
         let ray_direction = pixel_sample - ray_origin;
-        let ray_time = rng.gen_range(0.0..1.0); //send rays in a shutter period
+        // send rays in a shutter period, a closed one is an instant
+        let ray_time = if self.shutter_close > self.shutter_open {
+            rng.gen_range(self.shutter_open..self.shutter_close)
+        } else {
+            self.shutter_open
+        };
 
         Ray::new(ray_origin, ray_direction, ray_time)
     }
+    pub fn image_height(&self) -> u32 {
+        self.image_height
+    }
     //Returns a random point in the camera defocus disk
     fn defocus_disk_sample(&self) -> Point3 {
         let p = random_in_unit_disk();
         self.center + self.defocus_disk_u * p.x() + self.defocus_disk_v * p.y()
     }
-    fn sample_square_stratified(&self, s_i: u32, s_j: u32, rng: &mut ThreadRng) -> Vec3 {
+    fn sample_square_stratified(&self, s_i: u32, s_j: u32, rng: &mut LocalRng) -> Vec3 {
         // return a vec to the specified sub-pixel
         let px = (s_i as f64 + rng.gen_range(0.0..1.0)) * self.recip_sqrt_spp - 0.5;
         let py = (s_j as f64 + rng.gen_range(0.0..1.0)) * self.recip_sqrt_spp - 0.5;
@@ -0,0 +1,285 @@
+// command line parsing for the raytracer binary
+
+use crate::render::adaptive::AdaptiveParam;
+use crate::render::checkpoint::{CheckpointParam, DEFAULT_CHECKPOINT_SECS};
+use crate::render::PassParam;
+use crate::util::tone_map::ToneParam;
+use crate::util::{RenderParam, INFINITY};
+use crate::worlds::{ANIMATIONS, SCENES};
+
+pub const USAGE: &str = "\
+Usage: raytracer [OPTIONS]
+
+Options:
+  -s, --scene <NAME>      scene to render (default: final_scene)
+  -f, --scene-file <FILE> render a scene described in a TOML file instead, or
+                          import a glTF 2.0 one (.gltf or .glb)
+      --export <FILE>     write the scene to a TOML scene file instead of rendering
+      --frames <RANGE>    render an animated scene frame by frame, FIRST:LAST, one
+                          frame N, or all; frames are saved as frame_0001.png and
+                          so on next to the --output file, in its format
+  -w, --width <PIXELS>    image width
+  -a, --aspect <RATIO>    aspect ratio, e.g. 1.5 or 16:9
+      --spp <N>           samples per pixel
+  -d, --depth <N>         max recursion depth of a ray
+  -j, --threads <N>       number of render threads
+      --bvh <STRATEGY>    how BVHs are built: median or sah (default: up to the scene)
+      --bvh-layout <LAYOUT>
+                          linear (default, one flat array) or tree (boxed nodes)
+      --bvh-stats         print build time and quality of every BVH built
+      --seed <N>          seed for scene building and rendering; the same seed gives
+                          the same image (default: a random one)
+      --tile-size <PIXELS>
+                          edge length of the square tiles handed to the threads (default: 32)
+      --tile-order <ORDER>
+                          scanline, hilbert (default) or spiral (center first)
+      --preview-passes <N>
+                          render in passes, writing the image so far every N passes
+      --preview-secs <SECS>
+                          render in passes, writing the image so far every SECS seconds
+      --adaptive <ERROR>  stop sampling a pixel once its relative error is below ERROR,
+                          e.g. 0.01, and spend the saved samples on noisy pixels
+      --adaptive-min <N>  samples every pixel takes before it may stop (default: 16)
+      --adaptive-max <N>  samples a noisy pixel may take at most (default: 4 x spp)
+      --spectral          trace each path at one wavelength, so glass with dispersion
+                          splits light into colors
+      --sample-map <FILE> save an image of the samples taken per pixel
+      --checkpoint <FILE> save the accumulated samples to FILE while rendering
+      --checkpoint-secs <SECS>
+                          how often the checkpoint is saved (default: 60)
+      --resume            continue the render saved in the --checkpoint file
+  -o, --output <FILE>     where to save the image (default: output/book0/202.png);
+                          .exr, .hdr and .pfm keep the linear float radiance
+      --tone-map <OP>     tone mapping for 8-bit output: clamp (default), reinhard,
+                          reinhard:<WHITE>, aces or hable
+      --exposure <STOPS>  scale the radiance by 2^STOPS before tone mapping
+      --edge-detect       draw detected edges over the image
+  -l, --list-scenes       print the available scenes and exit
+  -h, --help              print this message and exit";
+
+pub struct CliArgs {
+    pub scene: String,
+    pub scene_file: Option<String>,
+    pub export: Option<String>,
+    pub frames: Option<Frames>,
+    pub render: RenderParam,
+    pub passes: PassParam,
+    pub output: String,
+    pub sample_map: Option<String>,
+    pub tone: ToneParam,
+    pub edge_detect: bool,
+    pub bvh_stats: bool,
+    pub list_scenes: bool,
+    pub help: bool,
+}
+
+impl Default for CliArgs {
+    fn default() -> Self {
+        CliArgs {
+            scene: String::from("final_scene"),
+            scene_file: None,
+            export: None,
+            frames: None,
+            render: RenderParam::default(),
+            passes: PassParam::default(),
+            output: String::from("output/book0/202.png"),
+            sample_map: None,
+            tone: ToneParam::default(),
+            edge_detect: false,
+            bvh_stats: false,
+            list_scenes: false,
+            help: false,
+        }
+    }
+}
+
+impl CliArgs {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<CliArgs, String> {
+        let mut ret = CliArgs::default();
+        let mut checkpoint = None;
+        let mut checkpoint_secs = DEFAULT_CHECKPOINT_SECS;
+        let mut resume = false;
+        let mut seed = None;
+        let mut adaptive = None;
+        let mut adaptive_min = None;
+        let mut adaptive_max = None;
+
+        while let Some(arg) = args.next() {
+            // accept both "--key value" and "--key=value"
+            let (key, inline_value) = match arg.split_once('=') {
+                Some((k, v)) if k.starts_with("--") => (String::from(k), Some(String::from(v))),
+                _ => (arg, None),
+            };
+            let mut value = |name: &str| -> Result<String, String> {
+                match inline_value.clone() {
+                    Some(v) => Ok(v),
+                    None => args
+                        .next()
+                        .ok_or_else(|| format!("missing value for '{}'", name)),
+                }
+            };
+
+            match key.as_str() {
+                "-s" | "--scene" => ret.scene = value(&key)?,
+                "-f" | "--scene-file" => ret.scene_file = Some(value(&key)?),
+                "--export" => ret.export = Some(value(&key)?),
+                "--frames" => ret.frames = Some(value(&key)?.parse()?),
+                "-w" | "--width" => ret.render.image_width = parse_num(&key, &value(&key)?)?,
+                "-a" | "--aspect" => ret.render.aspect_ratio = parse_aspect(&value(&key)?)?,
+                "--spp" => ret.render.samples_per_pixel = parse_num(&key, &value(&key)?)?,
+                "-d" | "--depth" => ret.render.max_recurse_depth = parse_num(&key, &value(&key)?)?,
+                "-j" | "--threads" => ret.render.thread_num = parse_num(&key, &value(&key)?)?,
+                "--bvh" => ret.render.bvh = Some(value(&key)?.parse()?),
+                "--bvh-layout" => ret.render.bvh_layout = value(&key)?.parse()?,
+                "--bvh-stats" => ret.bvh_stats = true,
+                "--seed" => seed = Some(parse_num(&key, &value(&key)?)?),
+                "--tile-size" => ret.passes.tile_size = parse_num(&key, &value(&key)?)?,
+                "--tile-order" => ret.passes.tile_order = value(&key)?.parse()?,
+                "--spectral" => ret.passes.spectral = true,
+                "--preview-passes" => {
+                    ret.passes.preview.every_passes = Some(parse_num(&key, &value(&key)?)?)
+                }
+                "--preview-secs" => {
+                    ret.passes.preview.every_secs = Some(parse_num(&key, &value(&key)?)?)
+                }
+                "--adaptive" => adaptive = Some(parse_num(&key, &value(&key)?)?),
+                "--adaptive-min" => adaptive_min = Some(parse_num(&key, &value(&key)?)?),
+                "--adaptive-max" => adaptive_max = Some(parse_num(&key, &value(&key)?)?),
+                "--sample-map" => ret.sample_map = Some(value(&key)?),
+                "--checkpoint" => checkpoint = Some(value(&key)?),
+                "--checkpoint-secs" => checkpoint_secs = parse_num(&key, &value(&key)?)?,
+                "--resume" => resume = true,
+                "-o" | "--output" => ret.output = value(&key)?,
+                "--tone-map" => ret.tone.tone_map = value(&key)?.parse()?,
+                "--exposure" => ret.tone.exposure = parse_num(&key, &value(&key)?)?,
+                "--edge-detect" => ret.edge_detect = true,
+                "-l" | "--list-scenes" => ret.list_scenes = true,
+                "-h" | "--help" => ret.help = true,
+                _ => return Err(format!("unknown option '{}'", key)),
+            }
+        }
+
+        if ret.render.image_width == 0 {
+            return Err(String::from("image width must be positive"));
+        }
+        if ret.render.samples_per_pixel == 0 {
+            return Err(String::from("samples per pixel must be positive"));
+        }
+        if ret.render.max_recurse_depth <= 0 {
+            return Err(String::from("max depth must be positive"));
+        }
+        if ret.passes.preview.every_passes == Some(0) {
+            return Err(String::from("preview passes must be positive"));
+        }
+        if let Some(secs) = ret.passes.preview.every_secs {
+            if !secs.is_finite() || secs <= 0.0 {
+                return Err(String::from("preview seconds must be positive"));
+            }
+        }
+        ret.render.seed = seed.unwrap_or_else(rand::random);
+        if let Some(threshold) = adaptive {
+            if !(threshold > 0.0 && threshold < INFINITY) {
+                return Err(String::from("adaptive error threshold must be positive"));
+            }
+            let mut param = AdaptiveParam::new(threshold);
+            if let Some(min) = adaptive_min {
+                if min == 0 {
+                    return Err(String::from("adaptive minimum samples must be positive"));
+                }
+                param.min_passes = min;
+            }
+            param.max_spp = adaptive_max;
+            ret.passes.adaptive = Some(param);
+        } else if adaptive_min.is_some() || adaptive_max.is_some() {
+            return Err(String::from(
+                "--adaptive-min/--adaptive-max need --adaptive",
+            ));
+        }
+        if !checkpoint_secs.is_finite() || checkpoint_secs < 0.0 {
+            return Err(String::from("checkpoint seconds must not be negative"));
+        }
+        ret.passes.checkpoint = match (checkpoint, resume) {
+            (Some(path), resume) => Some(CheckpointParam {
+                path,
+                every_secs: checkpoint_secs,
+                resume,
+            }),
+            (None, true) => return Err(String::from("--resume needs a --checkpoint file")),
+            (None, false) => None,
+        };
+        if ret.frames.is_some() && ret.passes.checkpoint.is_some() {
+            return Err(String::from(
+                "--checkpoint only works for single images, not --frames",
+            ));
+        }
+        if !ret.tone.exposure.is_finite() {
+            return Err(String::from("exposure must be finite"));
+        }
+        if ret.passes.tile_size == 0 {
+            return Err(String::from("tile size must be positive"));
+        }
+        if ret.render.thread_num == 0 {
+            return Err(String::from("thread number must be positive"));
+        }
+        Ok(ret)
+    }
+}
+
+pub fn print_scenes() {
+    println!("Available scenes:");
+    for (name, _) in SCENES.iter() {
+        println!("  {}", name);
+    }
+    println!("Animated scenes, for --frames:");
+    for (name, _) in ANIMATIONS.iter() {
+        println!("  {}", name);
+    }
+}
+
+// which frames --frames renders, both ends included
+#[derive(Clone, Copy)]
+pub enum Frames {
+    // the range the animation comes with
+    All,
+    Range(u32, u32),
+}
+
+impl std::str::FromStr for Frames {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "all" {
+            return Ok(Frames::All);
+        }
+        let (first, last) = match s.split_once(':') {
+            Some((first, last)) => (parse_num("--frames", first)?, parse_num("--frames", last)?),
+            None => {
+                let frame = parse_num("--frames", s)?;
+                (frame, frame)
+            }
+        };
+        if first > last {
+            return Err(format!("frame range '{}' ends before it starts", s));
+        }
+        Ok(Frames::Range(first, last))
+    }
+}
+
+fn parse_num<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("invalid value '{}' for '{}'", value, key))
+}
+
+fn parse_aspect(value: &str) -> Result<f64, String> {
+    let ratio = if let Some((w, h)) = value.split_once(':') {
+        let w: f64 = parse_num("--aspect", w)?;
+        let h: f64 = parse_num("--aspect", h)?;
+        w / h
+    } else {
+        parse_num("--aspect", value)?
+    };
+    if !ratio.is_finite() || ratio <= 0.0 {
+        return Err(format!("invalid aspect ratio '{}'", value));
+    }
+    Ok(ratio)
+}
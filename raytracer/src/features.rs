@@ -1,3 +1,22 @@
 pub mod edge_detect;
+pub mod gltf;
+pub mod mesh_file;
 pub mod normal_map;
 pub mod obj_mesh;
+
+use std::fmt;
+
+// what goes wrong reading a model or scene file
+#[derive(Debug)]
+pub struct LoadError {
+    pub file: String,
+    pub msg: String,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.file, self.msg)
+    }
+}
+
+impl std::error::Error for LoadError {}
@@ -0,0 +1,786 @@
+// import a glTF 2.0 scene, .gltf (buffers in files or data uris) or .glb:
+// the node tree, triangle meshes, metallic-roughness materials, the first
+// camera and KHR_lights_punctual lights. like the .mtl translation, the
+// materials become the crate's own: emissive ones lights, transmissive
+// ones glass (rough and tinted by KHR_materials_volume), anything else
+// microfacet, and the punctual lights small emitting spheres, since the
+// renderer only knows lights it can hit. parsing and validating the file
+// is left to the gltf crate
+
+use crate::{
+    camera::{background::BackGround, Camera},
+    features::{
+        normal_map::{MapMap, NormalMap},
+        LoadError,
+    },
+    hittable::{
+        bvh::{build_bvh, BvhLayout, BvhStrategy},
+        hittable_list::HittableList,
+        instances::{
+            sphere::Sphere,
+            triangle_mesh::{MeshBuffers, Submesh, TriangleMesh},
+        },
+        transforms::instance::Instance,
+        Hittable,
+    },
+    materials::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian,
+        microfacet::Microfacet, Material,
+    },
+    textures::{image_texture::ImageTexture, SolidColor, Texture},
+    util::{aabb::Aabb, color::Color, mat4::Mat4, vec3::*, RenderParam, PI},
+    worlds::Scene,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use gltf::{
+    accessor::{DataType, Item, Iter},
+    camera::Projection,
+    khr_lights_punctual::{Kind, Light},
+    mesh::Mode,
+    scene::Transform,
+    Accessor, Document, Gltf, Node,
+};
+use image::RgbImage;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+// anything else listed in extensionsRequired is refused
+const SUPPORTED_EXTENSIONS: [&str; 6] = [
+    "KHR_lights_punctual",
+    "KHR_materials_emissive_strength",
+    "KHR_materials_ior",
+    "KHR_materials_transmission",
+    "KHR_materials_volume",
+    "KHR_mesh_quantization",
+];
+
+// the spec defaults
+const DEFAULT_IOR: f64 = 1.5;
+const DEFAULT_GRAY: f64 = 0.8;
+
+// point lights become spheres this big, relative to the scene's radius
+const POINT_LIGHT_SIZE: f64 = 0.005;
+// the sun sits this many scene radii away and looks about as big as ours
+const SUN_DISTANCE: f64 = 100.0;
+const SUN_ANGLE: f64 = 0.0047; // radians, angular radius
+
+const DEFAULT_VFOV: f64 = 40.0;
+
+pub fn load_gltf(path: &str, param: &RenderParam) -> Result<Scene, LoadError> {
+    let error = |msg: String| LoadError {
+        file: String::from(path),
+        msg,
+    };
+    let data = fs::read(path).map_err(|err| error(format!("cannot read: {}", err)))?;
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    Importer::new(&data, dir, param)
+        .and_then(|mut importer| importer.scene(param))
+        .map_err(error)
+}
+
+// what the node tree places where
+#[derive(Default)]
+struct Placed<'a> {
+    meshes: Vec<(gltf::Mesh<'a>, Mat4)>,
+    camera: Option<(gltf::Camera<'a>, Mat4)>,
+    lights: Vec<(Light<'a>, Mat4)>,
+}
+
+type Translated = (Arc<dyn Material>, Option<Arc<dyn NormalMap>>);
+
+struct Importer {
+    // shared so the document can be walked while the importer fills its caches
+    doc: Rc<Document>,
+    dir: PathBuf,
+    buffers: Vec<Vec<u8>>,
+    strategy: BvhStrategy,
+    layout: BvhLayout,
+    // decoded once, however many materials use them
+    meshes: HashMap<usize, Option<Arc<dyn Hittable>>>,
+    materials: HashMap<usize, Translated>,
+    images: HashMap<usize, RgbImage>,
+    color_maps: HashMap<usize, Arc<ImageTexture>>,
+    normal_maps: HashMap<usize, Arc<MapMap>>,
+    emissive: bool,
+}
+
+impl Importer {
+    fn new(data: &[u8], dir: &Path, param: &RenderParam) -> Result<Importer, String> {
+        let Gltf { document, mut blob } =
+            Gltf::from_slice_without_validation(data).map_err(|err| err.to_string())?;
+        let mut root = document.into_json();
+        if !root.asset.version.starts_with("2.") {
+            return Err(format!("unsupported glTF version '{}'", root.asset.version));
+        }
+        // the crate only passes the extensions it reads itself, so the
+        // required ones are checked against what this importer reads
+        // before the rest of the file is validated
+        for ext in root.extensions_required.iter() {
+            if !SUPPORTED_EXTENSIONS.contains(&ext.as_str()) {
+                return Err(format!("unsupported required extension '{}'", ext));
+            }
+        }
+        root.extensions_required.clear();
+        let doc = Document::from_json(root).map_err(|err| err.to_string())?;
+
+        let mut buffers = Vec::new();
+        for buffer in doc.buffers() {
+            let i = buffer.index();
+            let data = match buffer.source() {
+                gltf::buffer::Source::Uri(uri) => read_uri(dir, uri)?,
+                gltf::buffer::Source::Bin => blob
+                    .take()
+                    .ok_or_else(|| format!("buffer {} has no uri and no BIN chunk", i))?,
+            };
+            if data.len() < buffer.length() {
+                return Err(format!(
+                    "buffer {} has {} bytes, expected {}",
+                    i,
+                    data.len(),
+                    buffer.length()
+                ));
+            }
+            buffers.push(data);
+        }
+
+        Ok(Importer {
+            doc: Rc::new(doc),
+            dir: dir.to_path_buf(),
+            buffers,
+            strategy: param.bvh.unwrap_or(BvhStrategy::Sah),
+            layout: param.bvh_layout,
+            meshes: HashMap::new(),
+            materials: HashMap::new(),
+            images: HashMap::new(),
+            color_maps: HashMap::new(),
+            normal_maps: HashMap::new(),
+            emissive: false,
+        })
+    }
+
+    fn scene(&mut self, param: &RenderParam) -> Result<Scene, String> {
+        let doc = self.doc.clone();
+        let roots: Vec<Node> = match doc.default_scene().or_else(|| doc.scenes().next()) {
+            Some(scene) => scene.nodes().collect(),
+            // no scene given: every node nobody claims as a child
+            None => {
+                let mut is_root = vec![true; doc.nodes().len()];
+                for child in doc.nodes().flat_map(|node| node.children()) {
+                    is_root[child.index()] = false;
+                }
+                doc.nodes().filter(|node| is_root[node.index()]).collect()
+            }
+        };
+        let mut placed = Placed::default();
+        for root in roots {
+            visit(root, &Mat4::IDENTITY, 0, doc.nodes().len(), &mut placed)?;
+        }
+
+        let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+        for (mesh, m) in placed.meshes.iter() {
+            // a node scaled to nothing, e.g. to hide it, shows nothing
+            if m.inverse().is_none() {
+                continue;
+            }
+            if let Some(mesh) = self.mesh(mesh)? {
+                objects.push(Box::new(Instance::new(mesh, *m)));
+            }
+        }
+        // lights and the default camera are sized after the meshes
+        let (center, radius) = if objects.is_empty() {
+            (Point3::default(), 1.0)
+        } else {
+            let bbox = objects
+                .iter()
+                .fold(Aabb::EMPTY, |b, o| Aabb::new_aabb(&b, o.bounding_box()));
+            let [x, y, z] = [0, 1, 2].map(|n| *bbox.axis_interval(n));
+            let half = Vec3::new(x.size(), y.size(), z.size()) / 2.0;
+            (
+                Point3::new(x.min, y.min, z.min) + half,
+                half.length().max(1e-6),
+            )
+        };
+
+        let mut world = HittableList::default();
+        let mut lights = HittableList::default();
+        if !objects.is_empty() {
+            world.add(build_bvh(objects, self.strategy, param.bvh_layout));
+        }
+        for (light, m) in placed.lights.iter() {
+            let (light_center, light_radius, emit) = punctual(light, m, center, radius);
+            let sphere = || {
+                Sphere::new_static(
+                    light_center,
+                    light_radius,
+                    DiffuseLight::<SolidColor>::new_color(emit),
+                )
+            };
+            world.add(Box::new(sphere()));
+            lights.add(Box::new(sphere()));
+        }
+
+        let cam = camera(placed.camera.as_ref(), center, radius, param);
+        // lit scenes are shown in the dark, unlit ones under the usual sky
+        let sky = if lights.objects.is_empty() && !self.emissive {
+            Color::new(0.5, 0.7, 1.0)
+        } else {
+            Color::default()
+        };
+        let back_ground = BackGround::new(Arc::new(SolidColor::new_color(sky)));
+        Ok((world, lights, cam, back_ground))
+    }
+
+    // a mesh in its own space, built once however often it is placed;
+    // None if it has no triangles
+    fn mesh(&mut self, mesh: &gltf::Mesh) -> Result<Option<Arc<dyn Hittable>>, String> {
+        if let Some(built) = self.meshes.get(&mesh.index()) {
+            return Ok(built.clone());
+        }
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut texs = Vec::new();
+        let mut submeshes = Vec::new();
+        for primitive in mesh.primitives() {
+            let Some(buffers) = self.primitive(mesh, &primitive)? else {
+                continue;
+            };
+            let base = positions.len() as u32;
+            positions.extend(buffers.positions);
+            normals.extend(buffers.normals);
+            texs.extend(buffers.texs);
+            let (material, normal_map) = self.material(primitive.material().index())?;
+            submeshes.push(Submesh {
+                indices: buffers
+                    .indices
+                    .iter()
+                    .map(|v| v.map(|i| base + i))
+                    .collect(),
+                material,
+                normal_map,
+            });
+        }
+        let built = (!submeshes.is_empty()).then(|| {
+            Arc::new(TriangleMesh::new(
+                positions,
+                normals,
+                texs,
+                Vec::new(),
+                submeshes,
+                self.strategy,
+                self.layout,
+            )) as Arc<dyn Hittable>
+        });
+        self.meshes.insert(mesh.index(), built.clone());
+        Ok(built)
+    }
+
+    // the vertices and faces of one primitive, None for points and lines
+    fn primitive(
+        &self,
+        mesh: &gltf::Mesh,
+        primitive: &gltf::Primitive,
+    ) -> Result<Option<MeshBuffers>, String> {
+        let error = |msg: &str| {
+            format!(
+                "mesh {} primitive {}: {}",
+                mesh.index(),
+                primitive.index(),
+                msg
+            )
+        };
+        let mode = primitive.mode();
+        if !matches!(
+            mode,
+            Mode::Triangles | Mode::TriangleStrip | Mode::TriangleFan
+        ) {
+            return Ok(None); // points and lines have no surface
+        }
+        let position = primitive
+            .get(&gltf::Semantic::Positions)
+            .ok_or_else(|| error("no POSITION"))?;
+        let positions: Vec<Point3> = self
+            .floats::<3>(&position)?
+            .into_iter()
+            .map(|[x, y, z]| Point3::new(x, y, z))
+            .collect();
+        let normals = match primitive.get(&gltf::Semantic::Normals) {
+            Some(normal) => self
+                .floats::<3>(&normal)?
+                .into_iter()
+                .map(|[x, y, z]| Vec3::new(x, y, z))
+                .collect(),
+            None => Vec::new(),
+        };
+        // glTF puts v = 0 at the top of the image
+        let texs = match primitive.get(&gltf::Semantic::TexCoords(0)) {
+            Some(tex) => self
+                .floats::<2>(&tex)?
+                .into_iter()
+                .map(|[u, v]| (u, 1.0 - v))
+                .collect(),
+            None => Vec::new(),
+        };
+        if normals.len() != positions.len() && !normals.is_empty()
+            || texs.len() != positions.len() && !texs.is_empty()
+        {
+            return Err(error("attributes of different lengths"));
+        }
+
+        let reader = primitive.reader(|buffer| self.buffers.get(buffer.index()).map(Vec::as_slice));
+        let order: Vec<u32> = match primitive.indices() {
+            Some(indices) => reader
+                .read_indices()
+                .ok_or_else(|| past_view(&indices))?
+                .into_u32()
+                .collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        if order.iter().any(|&i| i as usize >= positions.len()) {
+            return Err(error("index out of range"));
+        }
+        let indices: Vec<[u32; 3]> = match mode {
+            // every other strip triangle is wound the other way
+            Mode::TriangleStrip => order
+                .windows(3)
+                .enumerate()
+                .map(|(k, t)| {
+                    if k % 2 == 0 {
+                        [t[0], t[1], t[2]]
+                    } else {
+                        [t[1], t[0], t[2]]
+                    }
+                })
+                .collect(),
+            Mode::TriangleFan if !order.is_empty() => order
+                .windows(2)
+                .skip(1)
+                .map(|t| [order[0], t[0], t[1]])
+                .collect(),
+            Mode::TriangleFan => Vec::new(),
+            _ => order.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect(),
+        };
+
+        let mut buffers = MeshBuffers {
+            positions,
+            normals,
+            texs,
+            colors: Vec::new(),
+            indices,
+        };
+        // the spec asks for flat normals when there are none
+        if buffers.normals.is_empty() {
+            buffers.flat_normals();
+        }
+        if buffers.texs.is_empty() {
+            buffers.planar_uvs();
+        }
+        Ok(Some(buffers))
+    }
+
+    // the elements of an accessor as floats; KHR_mesh_quantization lets
+    // positions, normals and uvs be stored as integers, normalized or not
+    fn floats<const N: usize>(&self, accessor: &Accessor) -> Result<Vec<[f64; N]>, String>
+    where
+        [i8; N]: Item,
+        [u8; N]: Item,
+        [i16; N]: Item,
+        [u16; N]: Item,
+        [u32; N]: Item,
+        [f32; N]: Item,
+    {
+        let n = accessor.dimensions().multiplicity();
+        if n != N {
+            return Err(format!(
+                "accessor {} has {} components, expected {}",
+                accessor.index(),
+                n,
+                N
+            ));
+        }
+        match accessor.data_type() {
+            DataType::I8 => self.read::<i8, N>(accessor, 127.0),
+            DataType::U8 => self.read::<u8, N>(accessor, 255.0),
+            DataType::I16 => self.read::<i16, N>(accessor, 32767.0),
+            DataType::U16 => self.read::<u16, N>(accessor, 65535.0),
+            DataType::U32 => self.read::<u32, N>(accessor, u32::MAX as f64),
+            DataType::F32 => self.read::<f32, N>(accessor, 1.0),
+        }
+    }
+
+    // `max` is what a normalized integer divides by
+    fn read<T, const N: usize>(
+        &self,
+        accessor: &Accessor,
+        max: f64,
+    ) -> Result<Vec<[f64; N]>, String>
+    where
+        T: Copy + Into<f64>,
+        [T; N]: Item,
+    {
+        if accessor.count() == 0 {
+            return Ok(Vec::new());
+        }
+        let normalized = accessor.normalized();
+        let get = |buffer: gltf::Buffer| self.buffers.get(buffer.index()).map(Vec::as_slice);
+        let Some(iter) = Iter::<[T; N]>::new(accessor.clone(), get) else {
+            // without a buffer view everything is zero
+            if accessor.view().is_none() && accessor.sparse().is_none() {
+                return Ok(vec![[0.0; N]; accessor.count()]);
+            }
+            return Err(past_view(accessor));
+        };
+        Ok(iter
+            .map(|v| {
+                v.map(|x| {
+                    let x: f64 = x.into();
+                    if normalized {
+                        (x / max).max(-1.0)
+                    } else {
+                        x
+                    }
+                })
+            })
+            .collect())
+    }
+
+    fn material(&mut self, i: Option<usize>) -> Result<Translated, String> {
+        let Some(i) = i else {
+            let gray = Color::new(DEFAULT_GRAY, DEFAULT_GRAY, DEFAULT_GRAY);
+            return Ok((Arc::new(Lambertian::<SolidColor>::new_color(gray)), None));
+        };
+        if let Some(translated) = self.materials.get(&i) {
+            return Ok(translated.clone());
+        }
+        let translated = self
+            .translate(i)
+            .map_err(|msg| format!("material {}: {}", i, msg))?;
+        self.materials.insert(i, translated.clone());
+        Ok(translated)
+    }
+
+    fn translate(&mut self, i: usize) -> Result<Translated, String> {
+        let doc = self.doc.clone();
+        let mat = doc
+            .materials()
+            .nth(i)
+            .ok_or_else(|| String::from("does not exist"))?;
+        let pbr = mat.pbr_metallic_roughness();
+
+        let [r, g, b, _] = pbr.base_color_factor().map(f64::from);
+        let base = self.texture(
+            pbr.base_color_texture()
+                .map(|info| source(info.texture(), info.tex_coord())),
+            Color::new(r, g, b),
+        )?;
+        let metallic = pbr.metallic_factor() as f64;
+        let roughness = pbr.roughness_factor() as f64;
+        // one map holds both, roughness in green and metallic in blue
+        let metallic_roughness = self.texture(
+            pbr.metallic_roughness_texture()
+                .map(|info| source(info.texture(), info.tex_coord())),
+            Color::new(1.0, roughness, metallic),
+        )?;
+        let strength = mat.emissive_strength().unwrap_or(1.0) as f64;
+        let [r, g, b] = mat.emissive_factor().map(f64::from);
+        let emissive = Color::new(r, g, b) * strength;
+        let transmission = mat
+            .transmission()
+            .map_or(0.0, |t| t.transmission_factor() as f64);
+
+        let material: Arc<dyn Material> = if emissive.x().max(emissive.y()).max(emissive.z()) > 0.0
+        {
+            self.emissive = true;
+            let light = DiffuseLight::new_tex(
+                self.texture(
+                    mat.emissive_texture()
+                        .map(|info| source(info.texture(), info.tex_coord())),
+                    emissive,
+                )?,
+            );
+            // a double sided material glows on its back too
+            if mat.double_sided() {
+                Arc::new(light.two_sided())
+            } else {
+                Arc::new(light)
+            }
+        } else if transmission > 0.5 {
+            let ior = mat.ior().map_or(DEFAULT_IOR, f64::from);
+            // without KHR_materials_volume, or with no thickness, the glass
+            // is thin walled; with it, attenuationColor is what is left
+            // after attenuationDistance inside
+            let glass = Dielectric::new(ior);
+            let glass = match mat.volume() {
+                Some(volume) if volume.thickness_factor() > 0.0 => {
+                    let [r, g, b] = volume.attenuation_color().map(f64::from);
+                    let distance = volume.attenuation_distance() as f64;
+                    if distance.is_finite() && distance > 0.0 {
+                        glass.with_tint(Color::new(r, g, b), distance)
+                    } else {
+                        glass
+                    }
+                    .with_roughness(roughness)
+                }
+                _ => {
+                    if roughness > 0.0 {
+                        eprintln!(
+                            "warning: material {} is thin glass, which cannot be rough; \
+                             ignoring its roughness {:.2}",
+                            i, roughness
+                        );
+                    }
+                    glass.thin()
+                }
+            };
+            Arc::new(glass)
+        } else {
+            Arc::new(Microfacet::new_tex(
+                base,
+                Channel {
+                    tex: metallic_roughness.clone(),
+                    channel: 2,
+                },
+                Channel {
+                    tex: metallic_roughness,
+                    channel: 1,
+                },
+            ))
+        };
+
+        let normal_map = match mat.normal_texture() {
+            Some(info) => {
+                let image = source(info.texture(), info.tex_coord());
+                Some(self.normal_map(image)? as Arc<dyn NormalMap>)
+            }
+            None => None,
+        };
+        Ok((material, normal_map))
+    }
+
+    // the image a texture shows scaled by its factor, or just the factor
+    fn texture(&mut self, image: Option<usize>, factor: Color) -> Result<Arc<dyn Texture>, String> {
+        let Some(image) = image else {
+            return Ok(Arc::new(SolidColor::new_color(factor)));
+        };
+        let tex = match self.color_maps.get(&image) {
+            Some(tex) => tex.clone(),
+            None => {
+                let tex = Arc::new(ImageTexture::_new_image(self.image(image)?.clone()));
+                self.color_maps.insert(image, tex.clone());
+                tex
+            }
+        };
+        Ok(Arc::new(Repeated { tex, factor }))
+    }
+
+    fn normal_map(&mut self, image: usize) -> Result<Arc<MapMap>, String> {
+        if let Some(nmap) = self.normal_maps.get(&image) {
+            return Ok(nmap.clone());
+        }
+        let nmap = Arc::new(MapMap::new_image(self.image(image)?.clone()));
+        self.normal_maps.insert(image, nmap.clone());
+        Ok(nmap)
+    }
+
+    fn image(&mut self, i: usize) -> Result<&RgbImage, String> {
+        if !self.images.contains_key(&i) {
+            let decoded = self.decode_image(i)?;
+            return Ok(self.images.entry(i).or_insert(decoded));
+        }
+        Ok(&self.images[&i])
+    }
+
+    fn decode_image(&self, i: usize) -> Result<RgbImage, String> {
+        let image = self
+            .doc
+            .images()
+            .nth(i)
+            .ok_or_else(|| format!("image {} does not exist", i))?;
+        let bytes = match image.source() {
+            gltf::image::Source::Uri { uri, .. } => read_uri(&self.dir, uri)?,
+            gltf::image::Source::View { view, .. } => self
+                .buffers
+                .get(view.buffer().index())
+                .and_then(|buffer| buffer.get(view.offset()..view.offset() + view.length()))
+                .ok_or_else(|| format!("buffer view {} runs past its buffer", view.index()))?
+                .to_vec(),
+        };
+        image::load_from_memory(&bytes)
+            .map(|image| image.to_rgb8())
+            .map_err(|err| format!("image {}: {}", i, err))
+    }
+}
+
+// `nodes` is how many the file has, deeper than that the tree loops
+fn visit<'a>(
+    node: Node<'a>,
+    parent: &Mat4,
+    depth: usize,
+    nodes: usize,
+    placed: &mut Placed<'a>,
+) -> Result<(), String> {
+    if depth > nodes {
+        return Err(String::from("the node hierarchy has a cycle"));
+    }
+    let local = match node.transform() {
+        Transform::Matrix { matrix } => {
+            let mut cols = [0.0; 16];
+            for (x, m) in cols.iter_mut().zip(matrix.iter().flatten()) {
+                *x = *m as f64;
+            }
+            Mat4::from_cols(cols)
+        }
+        Transform::Decomposed {
+            translation,
+            rotation,
+            scale,
+        } => Mat4::from_trs(
+            translation.map(f64::from),
+            rotation.map(f64::from),
+            scale.map(f64::from),
+        ),
+    };
+    let m = parent.mul(&local);
+
+    if let Some(mesh) = node.mesh() {
+        placed.meshes.push((mesh, m));
+    }
+    if let Some(camera) = node.camera() {
+        placed.camera.get_or_insert((camera, m));
+    }
+    if let Some(light) = node.light() {
+        placed.lights.push((light, m));
+    }
+    for child in node.children() {
+        visit(child, &m, depth + 1, nodes, placed)?;
+    }
+    Ok(())
+}
+
+// the image behind a texture
+fn source(texture: gltf::Texture, tex_coord: u32) -> usize {
+    if tex_coord != 0 {
+        eprintln!("warning: only TEXCOORD_0 is supported, using it for every texture");
+    }
+    texture.source().index()
+}
+
+fn past_view(accessor: &Accessor) -> String {
+    format!("accessor {}: runs past its buffer view", accessor.index())
+}
+
+// where the light sphere goes, its radius and what it emits
+fn punctual(light: &Light, m: &Mat4, center: Point3, radius: f64) -> (Point3, f64, Color) {
+    let [r, g, b] = light.color().map(f64::from);
+    let power = Color::new(r, g, b) * light.intensity() as f64;
+    match light.kind() {
+        // a sphere of radius r that looks as bright as the point light
+        // from afar; the spot cone is not modelled, it shines all round
+        Kind::Point | Kind::Spot { .. } => {
+            let r = radius * POINT_LIGHT_SIZE;
+            (m.point(&Point3::default()), r, power / (PI * r * r))
+        }
+        // a distant disc giving the same irradiance, E = L * pi * sin^2
+        Kind::Directional => {
+            let dir = unit_vector(&m.vector(&Vec3::new(0.0, 0.0, -1.0)));
+            let distance = radius * SUN_DISTANCE;
+            let sin = SUN_ANGLE.sin();
+            (
+                center - dir * distance,
+                distance * sin,
+                power / (PI * sin * sin),
+            )
+        }
+    }
+}
+
+fn camera(
+    placed: Option<&(gltf::Camera, Mat4)>,
+    center: Point3,
+    radius: f64,
+    param: &RenderParam,
+) -> Camera {
+    let mut cam = Camera::default();
+
+    cam.aspect_ratio = param.aspect_ratio;
+    cam.image_width = param.image_width;
+    cam.samples_per_pixel = param.samples_per_pixel;
+    cam.max_recurse_depth = param.max_recurse_depth;
+
+    match placed {
+        // glTF cameras look down -z with +y up
+        Some((camera, m)) => {
+            cam.vfov = match camera.projection() {
+                Projection::Perspective(perspective) => (perspective.yfov() as f64).to_degrees(),
+                Projection::Orthographic(_) => {
+                    eprintln!(
+                        "warning: camera {} is not perspective, using a {} degree view",
+                        camera.index(),
+                        DEFAULT_VFOV
+                    );
+                    DEFAULT_VFOV
+                }
+            };
+            cam.lookfrom = m.point(&Point3::default());
+            cam.lookat = cam.lookfrom + m.vector(&Vec3::new(0.0, 0.0, -1.0));
+            cam.vup = m.vector(&Vec3::new(0.0, 1.0, 0.0));
+        }
+        // no camera: look at the whole scene from the front
+        None => {
+            cam.vfov = DEFAULT_VFOV;
+            let distance = radius / (cam.vfov.to_radians() / 2.0).sin();
+            cam.lookfrom = center + Vec3::new(0.0, 0.0, distance);
+            cam.lookat = center;
+            cam.vup = Vec3::new(0.0, 1.0, 0.0);
+        }
+    }
+
+    cam.defocus_angle = 0.0;
+    cam.focus_dist = 10.0;
+
+    cam.initialize();
+    cam
+}
+
+// glTF textures repeat, and are scaled by the material's factor
+struct Repeated {
+    tex: Arc<ImageTexture>,
+    factor: Color,
+}
+
+impl Texture for Repeated {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.tex.value(u.rem_euclid(1.0), v.rem_euclid(1.0), p) * self.factor
+    }
+}
+
+// one channel of a texture, as a gray
+struct Channel {
+    tex: Arc<dyn Texture>,
+    channel: usize,
+}
+
+impl Texture for Channel {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let c = self.tex.value(u, v, p).e[self.channel];
+        Color::new(c, c, c)
+    }
+}
+
+// a base64 data uri, or a file next to the scene; file uris may escape
+// spaces and the like as %20
+fn read_uri(dir: &Path, uri: &str) -> Result<Vec<u8>, String> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, encoded) = data
+            .split_once(";base64,")
+            .ok_or_else(|| String::from("only base64 data uris are supported"))?;
+        return STANDARD
+            .decode(encoded)
+            .map_err(|err| format!("bad base64 in data uri: {}", err));
+    }
+    let name = urlencoding::decode(uri).map_err(|_| format!("uri '{}' is not utf-8", uri))?;
+    let path = dir.join(name.as_ref());
+    fs::read(&path).map_err(|err| format!("cannot read '{}': {}", path.display(), err))
+}
@@ -0,0 +1,147 @@
+// .ply and .stl meshes, placed like obj_mesh places an .obj. neither format
+// knows materials: a mesh with vertex colors gets them as its albedo, any
+// other one is plain gray
+
+mod ply;
+mod stl;
+
+use crate::{
+    features::{
+        obj_mesh::{place, LoadParam},
+        LoadError,
+    },
+    hittable::{
+        instances::triangle_mesh::{MeshBuffers, Submesh, TriangleMesh},
+        HitRecord, Hittable,
+    },
+    materials::{lambertian::Lambertian, Material},
+    textures::SolidColor,
+    util::{aabb::Aabb, color::Color, interval::Interval, ray::Ray, vec3::*},
+    worlds::scene_file::desc::{ObjectDesc, ShapeDesc},
+};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+const DEFAULT_GRAY: f64 = 0.8;
+
+// a loaded .ply or .stl, remembering where it came from like ObjMesh
+pub struct MeshFile {
+    mesh: TriangleMesh,
+    file: String,
+    zoom: f64,
+    offset: Vec3,
+    rot: [f64; 3],
+    flat_normals: bool,
+}
+
+impl Hittable for MeshFile {
+    fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
+        self.mesh.hit(r, ray_t, rec)
+    }
+    fn bounding_box(&self) -> &Aabb {
+        self.mesh.bounding_box()
+    }
+    fn describe(&self) -> Option<ObjectDesc> {
+        Some(
+            ShapeDesc::Mesh {
+                file: self.file.clone(),
+                zoom: self.zoom,
+                offset: self.offset.e,
+                rot_x: self.rot[0],
+                rot_y: self.rot[1],
+                rot_z: self.rot[2],
+                flat_normals: self.flat_normals,
+            }
+            .into(),
+        )
+    }
+}
+
+// picks the format by the extension
+pub fn mesh_file(param: LoadParam) -> Result<Box<dyn Hittable>, LoadError> {
+    let ext = Path::new(param.file)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    match ext.as_deref() {
+        Some("ply") => ply_mesh(param),
+        Some("stl") => stl_mesh(param),
+        _ => Err(LoadError {
+            file: String::from(param.file),
+            msg: String::from("not a .ply or .stl file"),
+        }),
+    }
+}
+
+pub fn ply_mesh(param: LoadParam) -> Result<Box<dyn Hittable>, LoadError> {
+    load(param, ply::read)
+}
+
+pub fn stl_mesh(param: LoadParam) -> Result<Box<dyn Hittable>, LoadError> {
+    load(param, stl::read)
+}
+
+fn load(
+    param: LoadParam,
+    read: impl FnOnce(&[u8]) -> Result<MeshBuffers, String>,
+) -> Result<Box<dyn Hittable>, LoadError> {
+    let error = |msg: String| LoadError {
+        file: String::from(param.file),
+        msg,
+    };
+    let data = fs::read(param.file).map_err(|err| error(format!("cannot read: {}", err)))?;
+    let mut buffers = read(&data).map_err(error)?;
+    if buffers.indices.is_empty() {
+        return Err(error(String::from("no faces")));
+    }
+    if buffers.positions.len() > u32::MAX as usize {
+        return Err(error(String::from("too many vertices")));
+    }
+
+    if buffers.normals.is_empty() {
+        if param.flat_normals {
+            buffers.flat_normals();
+        } else {
+            buffers.smooth_normals();
+        }
+    }
+    // the vertex colors are the albedo, otherwise it is plain gray
+    let albedo = if buffers.colors.is_empty() {
+        DEFAULT_GRAY
+    } else {
+        1.0
+    };
+    let material: Arc<dyn Material> = Arc::new(Lambertian::<SolidColor>::new_color(Color::new(
+        albedo, albedo, albedo,
+    )));
+
+    let MeshBuffers {
+        mut positions,
+        mut normals,
+        texs,
+        colors,
+        indices,
+    } = buffers;
+    place(&param, &mut positions, &mut normals, indices.iter());
+    let submesh = Submesh {
+        indices,
+        material,
+        normal_map: None,
+    };
+    Ok(Box::new(MeshFile {
+        mesh: TriangleMesh::new(
+            positions,
+            normals,
+            texs,
+            colors,
+            vec![submesh],
+            param.bvh,
+            param.layout,
+        ),
+        file: String::from(param.file),
+        zoom: param.zoom,
+        offset: param.offset,
+        rot: [param.rot_x, param.rot_y, param.rot_z],
+        flat_normals: param.flat_normals,
+    }))
+}
@@ -0,0 +1,422 @@
+// .ply as scanners and most mesh tools write it: a text header describing
+// elements and their properties, then the data in ascii or binary of either
+// endianness. vertices may carry normals and colors, faces are index lists
+// of any length, any other element is read past
+
+use crate::hittable::instances::triangle_mesh::MeshBuffers;
+use crate::util::{color::Color, vec3::*};
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Scalar, String> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return Err(format!("unknown property type '{}'", name)),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    // integer color channels are stored as 0..=max, floats as 0..=1
+    fn color_scale(self) -> f64 {
+        match self {
+            Scalar::I8 => i8::MAX as f64,
+            Scalar::U8 => u8::MAX as f64,
+            Scalar::I16 => i16::MAX as f64,
+            Scalar::U16 => u16::MAX as f64,
+            Scalar::I32 => i32::MAX as f64,
+            Scalar::U32 => u32::MAX as f64,
+            Scalar::F32 | Scalar::F64 => 1.0,
+        }
+    }
+}
+
+enum Property {
+    Scalar(Scalar, String),
+    // the type of the length, the type of the items
+    List(Scalar, Scalar, String),
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    // the position among the scalar properties of the first one named like this
+    fn scalar(&self, names: &[&str]) -> Option<(usize, Scalar)> {
+        self.properties
+            .iter()
+            .filter_map(|p| match p {
+                Property::Scalar(ty, name) => Some((*ty, name)),
+                Property::List(..) => None,
+            })
+            .enumerate()
+            .find(|(_, (_, name))| names.contains(&name.as_str()))
+            .map(|(i, (ty, _))| (i, ty))
+    }
+}
+
+enum Body<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary {
+        data: &'a [u8],
+        pos: usize,
+        big_endian: bool,
+    },
+}
+
+impl<'a> Body<'a> {
+    fn read(&mut self, ty: Scalar) -> Result<f64, String> {
+        match self {
+            Body::Ascii(words) => {
+                let word = words
+                    .next()
+                    .ok_or_else(|| String::from("file ends early"))?;
+                word.parse().map_err(|_| format!("bad number '{}'", word))
+            }
+            Body::Binary {
+                data,
+                pos,
+                big_endian,
+            } => {
+                let size = ty.size();
+                let bytes = data
+                    .get(*pos..*pos + size)
+                    .ok_or_else(|| String::from("file ends early"))?;
+                *pos += size;
+                // read everything as little endian
+                let mut b = [0u8; 8];
+                b[..size].copy_from_slice(bytes);
+                if *big_endian {
+                    b[..size].reverse();
+                }
+                Ok(match ty {
+                    Scalar::I8 => b[0] as i8 as f64,
+                    Scalar::U8 => b[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::F64 => f64::from_le_bytes(b),
+                })
+            }
+        }
+    }
+
+    // one item of an element: its scalars in order, lists handed to `list`
+    fn item(
+        &mut self,
+        element: &Element,
+        scalars: &mut Vec<f64>,
+        mut list: impl FnMut(&str, Vec<f64>),
+    ) -> Result<(), String> {
+        scalars.clear();
+        for property in element.properties.iter() {
+            match property {
+                Property::Scalar(ty, _) => scalars.push(self.read(*ty)?),
+                Property::List(len_ty, ty, name) => {
+                    let len = self.read(*len_ty)?;
+                    if len < 0.0 {
+                        return Err(format!("negative list length in '{}'", element.name));
+                    }
+                    let items = (0..len as usize)
+                        .map(|_| self.read(*ty))
+                        .collect::<Result<_, _>>()?;
+                    list(name, items);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn read(data: &[u8]) -> Result<MeshBuffers, String> {
+    let (body_start, big_endian, elements) = header(data)?;
+    let mut body = match big_endian {
+        None => Body::Ascii(
+            std::str::from_utf8(&data[body_start..])
+                .map_err(|_| String::from("ascii data is not text"))?
+                .split_ascii_whitespace(),
+        ),
+        Some(big_endian) => Body::Binary {
+            data: &data[body_start..],
+            pos: 0,
+            big_endian,
+        },
+    };
+
+    let mut buffers = MeshBuffers {
+        positions: Vec::new(),
+        normals: Vec::new(),
+        texs: Vec::new(),
+        colors: Vec::new(),
+        indices: Vec::new(),
+    };
+    let mut scalars = Vec::new();
+    for element in elements.iter() {
+        match element.name.as_str() {
+            "vertex" => {
+                let position = ["x", "y", "z"].map(|c| element.scalar(&[c]));
+                let normal = ["nx", "ny", "nz"].map(|c| element.scalar(&[c]));
+                let color = ["red", "green", "blue"]
+                    .map(|c| element.scalar(&[c, &format!("diffuse_{}", c)]));
+                let [Some(x), Some(y), Some(z)] = position else {
+                    return Err(String::from("vertices without x, y and z"));
+                };
+                for _ in 0..element.count {
+                    body.item(element, &mut scalars, |_, _| {})?;
+                    buffers
+                        .positions
+                        .push(Point3::new(scalars[x.0], scalars[y.0], scalars[z.0]));
+                    if let [Some(nx), Some(ny), Some(nz)] = normal {
+                        buffers.normals.push(Vec3::new(
+                            scalars[nx.0],
+                            scalars[ny.0],
+                            scalars[nz.0],
+                        ));
+                    }
+                    if let [Some(r), Some(g), Some(b)] = color {
+                        let c = |(i, ty): (usize, Scalar)| scalars[i] / ty.color_scale();
+                        buffers.colors.push(Color::new(c(r), c(g), c(b)));
+                    }
+                }
+            }
+            "face" => {
+                let mut bad_list = None;
+                for _ in 0..element.count {
+                    body.item(element, &mut scalars, |name, items| {
+                        if name != "vertex_indices" && name != "vertex_index" {
+                            return;
+                        }
+                        if items.len() < 3 {
+                            bad_list.get_or_insert(items.len());
+                            return;
+                        }
+                        // polygons become fans around their first corner
+                        for k in 1..items.len() - 1 {
+                            let face = [items[0], items[k], items[k + 1]];
+                            // negative ones are caught with the out of range ones below
+                            let index = |i: f64| if i < 0.0 { u32::MAX } else { i as u32 };
+                            buffers.indices.push(face.map(index));
+                        }
+                    })?;
+                }
+                if let Some(len) = bad_list {
+                    eprintln!("warning: skipped faces with {} corners", len);
+                }
+            }
+            _ => {
+                for _ in 0..element.count {
+                    body.item(element, &mut scalars, |_, _| {})?;
+                }
+            }
+        }
+    }
+
+    let count = buffers.positions.len();
+    if buffers
+        .indices
+        .iter()
+        .flatten()
+        .any(|&i| i as usize >= count)
+    {
+        return Err(String::from("face index out of range"));
+    }
+    Ok(buffers)
+}
+
+// where the data starts, None for ascii or whether it is big endian, and
+// the elements in file order
+fn header(data: &[u8]) -> Result<(usize, Option<bool>, Vec<Element>), String> {
+    let mut pos = 0;
+    let mut first = true;
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    loop {
+        let end = data[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map(|len| pos + len)
+            .ok_or_else(|| String::from("header has no end_header"))?;
+        let line = std::str::from_utf8(&data[pos..end])
+            .map_err(|_| String::from("header is not text"))?
+            .trim();
+        pos = end + 1;
+        if first {
+            if line != "ply" {
+                return Err(String::from("not a ply file"));
+            }
+            first = false;
+            continue;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["format", "ascii", _] => format = Some(None),
+            ["format", "binary_little_endian", _] => format = Some(Some(false)),
+            ["format", "binary_big_endian", _] => format = Some(Some(true)),
+            ["element", name, count] => elements.push(Element {
+                name: String::from(name),
+                count: count
+                    .parse()
+                    .map_err(|_| format!("bad count in '{}'", line))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", len_ty, ty, name] => elements
+                .last_mut()
+                .ok_or_else(|| format!("property before any element: '{}'", line))?
+                .properties
+                .push(Property::List(
+                    Scalar::parse(len_ty)?,
+                    Scalar::parse(ty)?,
+                    String::from(name),
+                )),
+            ["property", ty, name] => elements
+                .last_mut()
+                .ok_or_else(|| format!("property before any element: '{}'", line))?
+                .properties
+                .push(Property::Scalar(Scalar::parse(ty)?, String::from(name))),
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(format!("bad header line '{}'", line)),
+        }
+    }
+    let format = format.ok_or_else(|| String::from("header has no format"))?;
+    Ok((pos, format, elements))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a unit square in z = 0 with a colored corner, and an edge element
+    // the reader has to step over
+    fn header(format: &str) -> String {
+        format!(
+            "ply\nformat {} 1.0\ncomment made by hand\n\
+             element vertex 4\nproperty float x\nproperty float y\nproperty float z\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\n\
+             element edge 1\nproperty int vertex1\nproperty int vertex2\n\
+             element face 1\nproperty list uchar int vertex_indices\nend_header\n",
+            format
+        )
+    }
+
+    const CORNERS: [[f32; 3]; 4] = [
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+    ];
+
+    fn ascii() -> Vec<u8> {
+        let body = "0 0 0 255 0 0\n1 0 0 255 255 255\n1 1 0 255 255 255\n0 1 0 255 255 255\n\
+                    0 1\n\
+                    4 0 1 2 3\n";
+        (header("ascii") + body).into_bytes()
+    }
+
+    fn binary(big_endian: bool) -> Vec<u8> {
+        let format = match big_endian {
+            true => "binary_big_endian",
+            false => "binary_little_endian",
+        };
+        let mut data = header(format).into_bytes();
+        let mut put = |bytes: &[u8]| match big_endian {
+            true => data.extend(bytes.iter().rev()),
+            false => data.extend(bytes),
+        };
+        for (i, corner) in CORNERS.iter().enumerate() {
+            for x in corner {
+                put(&x.to_le_bytes());
+            }
+            let green = if i == 0 { 0 } else { 255 };
+            put(&[255]);
+            put(&[green]);
+            put(&[green]);
+        }
+        put(&0i32.to_le_bytes());
+        put(&1i32.to_le_bytes());
+        put(&[4]);
+        for i in 0..4i32 {
+            put(&i.to_le_bytes());
+        }
+        data
+    }
+
+    fn check(buffers: &MeshBuffers) {
+        let positions: Vec<_> = buffers.positions.iter().map(|p| p.e).collect();
+        assert_eq!(positions, CORNERS.map(|c| c.map(|x| x as f64)));
+        assert!(buffers.normals.is_empty());
+        assert_eq!(buffers.colors.len(), 4);
+        assert_eq!(buffers.colors[0].e, [1.0, 0.0, 0.0]);
+        assert_eq!(buffers.colors[1].e, [1.0, 1.0, 1.0]);
+        // the quad is split into a fan
+        assert_eq!(buffers.indices, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn reads_every_format() {
+        check(&read(&ascii()).unwrap());
+        check(&read(&binary(false)).unwrap());
+        check(&read(&binary(true)).unwrap());
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        let err = |data: &[u8]| read(data).err().unwrap();
+
+        let out_of_range = String::from_utf8(ascii())
+            .unwrap()
+            .replace("4 0 1 2 3", "3 0 1 7");
+        assert_eq!(err(out_of_range.as_bytes()), "face index out of range");
+        let negative = String::from_utf8(ascii())
+            .unwrap()
+            .replace("4 0 1 2 3", "3 0 1 -1");
+        assert_eq!(err(negative.as_bytes()), "face index out of range");
+
+        let flat = String::from_utf8(ascii())
+            .unwrap()
+            .replace("property float z\n", "");
+        assert_eq!(err(flat.as_bytes()), "vertices without x, y and z");
+
+        let mut short = binary(true);
+        short.pop();
+        assert_eq!(err(&short), "file ends early");
+
+        assert_eq!(err(b"solid cube\n"), "not a ply file");
+        assert_eq!(err(b"ply\nelement vertex 0\n"), "header has no end_header");
+        assert_eq!(err(b"ply\nend_header\n"), "header has no format");
+        assert_eq!(
+            err(b"ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\nend_header\n"),
+            "unknown property type 'half'"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+// .stl, as CAD tools export parts: a bare list of triangles, in ascii or in
+// binary (an 80 byte header, a count, then 50 bytes per triangle). the facet
+// normals are often wrong or zero, so they are ignored like most viewers do
+
+use crate::hittable::instances::triangle_mesh::MeshBuffers;
+use crate::util::vec3::*;
+use std::collections::HashMap;
+
+pub fn read(data: &[u8]) -> Result<MeshBuffers, String> {
+    // binary files may start with "solid" too, their size gives them away
+    let binary_len = data
+        .get(80..84)
+        .map(|n| 84 + 50 * u32::from_le_bytes([n[0], n[1], n[2], n[3]]) as usize);
+    let corners = if binary_len == Some(data.len()) {
+        binary(data)
+    } else if data.starts_with(b"solid") {
+        ascii(&String::from_utf8_lossy(data))?
+    } else {
+        return Err(String::from("neither an ascii nor a binary stl"));
+    };
+    Ok(weld(&corners))
+}
+
+fn binary(data: &[u8]) -> Vec<Point3> {
+    let mut corners = Vec::new();
+    for facet in data[84..].chunks_exact(50) {
+        // the corners sit between the normal and the attribute bytes
+        for corner in facet[12..48].chunks_exact(12) {
+            let float = |i: usize| {
+                let b = &corner[4 * i..4 * i + 4];
+                f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+            };
+            corners.push(Point3::new(float(0), float(1), float(2)));
+        }
+    }
+    corners
+}
+
+fn ascii(text: &str) -> Result<Vec<Point3>, String> {
+    let mut corners = Vec::new();
+    let mut words = text.split_ascii_whitespace();
+    while let Some(word) = words.next() {
+        if word != "vertex" {
+            continue;
+        }
+        let mut p = [0.0; 3];
+        for x in p.iter_mut() {
+            *x = words
+                .next()
+                .and_then(|w| w.parse().ok())
+                .ok_or_else(|| format!("bad vertex after {} corners", corners.len()))?;
+        }
+        corners.push(Point3::new(p[0], p[1], p[2]));
+    }
+    if corners.len() % 3 != 0 {
+        return Err(String::from("a facet without three vertices"));
+    }
+    Ok(corners)
+}
+
+// every triangle repeats its corners, sharing equal ones gives smooth
+// normals something to average over
+fn weld(corners: &[Point3]) -> MeshBuffers {
+    let mut ids = HashMap::new();
+    let mut positions = Vec::new();
+    let mut indices = Vec::with_capacity(corners.len() / 3);
+    for triangle in corners.chunks_exact(3) {
+        let mut face = [0u32; 3];
+        for (id, p) in face.iter_mut().zip(triangle) {
+            // adding 0 turns -0 into 0, they are the same point
+            let key = p.e.map(|x| (x + 0.0).to_bits());
+            *id = *ids.entry(key).or_insert_with(|| {
+                positions.push(*p);
+                positions.len() as u32 - 1
+            });
+        }
+        indices.push(face);
+    }
+    MeshBuffers {
+        positions,
+        normals: Vec::new(),
+        texs: Vec::new(),
+        colors: Vec::new(),
+        indices,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // two triangles sharing the diagonal of a unit square
+    const TRIANGLES: [[[f32; 3]; 3]; 2] = [
+        [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+        [[0.0, 0.0, -0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+    ];
+
+    fn ascii() -> String {
+        let mut text = String::from("solid square\n");
+        for triangle in TRIANGLES {
+            text += "facet normal 0 0 1\nouter loop\n";
+            for [x, y, z] in triangle {
+                text += &format!("vertex {} {} {}\n", x, y, z);
+            }
+            text += "endloop\nendfacet\n";
+        }
+        text + "endsolid square\n"
+    }
+
+    fn binary() -> Vec<u8> {
+        // a header starting with "solid" like some exporters write
+        let mut data = b"solid square".to_vec();
+        data.resize(80, 0);
+        data.extend(2u32.to_le_bytes());
+        for triangle in TRIANGLES {
+            // the normal is left at zero
+            data.extend([0; 12]);
+            for x in triangle.iter().flatten() {
+                data.extend(x.to_le_bytes());
+            }
+            data.extend([0; 2]);
+        }
+        data
+    }
+
+    fn check(buffers: &MeshBuffers) {
+        // the shared corners are welded, -0 included
+        assert_eq!(buffers.positions.len(), 4);
+        assert_eq!(buffers.indices, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(buffers.positions[3].e, [0.0, 1.0, 0.0]);
+        assert!(buffers.normals.is_empty() && buffers.colors.is_empty());
+    }
+
+    #[test]
+    fn reads_ascii_and_binary() {
+        check(&read(ascii().as_bytes()).unwrap());
+        check(&read(&binary()).unwrap());
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        let err = |data: &[u8]| read(data).err().unwrap();
+
+        // cut short, the size no longer matches the count
+        let mut short = binary();
+        short[..5].copy_from_slice(b"model");
+        short.pop();
+        assert_eq!(err(&short), "neither an ascii nor a binary stl");
+
+        let bad = ascii().replace("vertex 1 1 0", "vertex 1 one 0");
+        assert_eq!(err(bad.as_bytes()), "bad vertex after 2 corners");
+
+        let missing = ascii().replacen("vertex 0 0 0\n", "", 1);
+        assert_eq!(err(missing.as_bytes()), "a facet without three vertices");
+    }
+}
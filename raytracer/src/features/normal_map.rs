@@ -1,5 +1,6 @@
 use crate::util::{image_process::process_pixels, onb::Onb, vec3::Vec3};
 use image::RgbImage;
+use std::sync::Arc;
 
 // normal mapping
 pub trait NormalMap: Send + Sync {
@@ -9,6 +10,22 @@ pub trait NormalMap: Send + Sync {
     fn convert(&self, (_u0, _v0): (f64, f64), (_w, _h): (f64, f64)) -> (u32, u32) {
         (0, 0)
     }
+    // image path for a scene file, None means the normal is left unchanged
+    fn describe(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<N: NormalMap + ?Sized> NormalMap for Arc<N> {
+    fn modify_normal(&self, uv: (u32, u32), wtb: Onb) -> Vec3 {
+        (**self).modify_normal(uv, wtb)
+    }
+    fn convert(&self, uv0: (f64, f64), wh: (f64, f64)) -> (u32, u32) {
+        (**self).convert(uv0, wh)
+    }
+    fn describe(&self) -> Option<String> {
+        (**self).describe()
+    }
 }
 
 // do nothing
@@ -22,6 +39,7 @@ pub struct MapMap {
     width: u32,
     height: u32,
     nmap: RgbImage,
+    path: Option<String>, // None if not loaded from a file
 }
 
 impl MapMap {
@@ -31,6 +49,16 @@ impl MapMap {
             width,
             height,
             nmap,
+            path: Some(String::from(image_path)),
+        }
+    }
+    pub fn new_image(nmap: RgbImage) -> MapMap {
+        let (width, height) = nmap.dimensions();
+        MapMap {
+            width,
+            height,
+            nmap,
+            path: None,
         }
     }
 }
@@ -54,4 +82,7 @@ impl NormalMap for MapMap {
             self.height - 1 - (v0 / h * self.height as f64) as u32, // 为什么会倒反天罡呢？
         )
     }
+    fn describe(&self) -> Option<String> {
+        self.path.clone()
+    }
 }
@@ -1,93 +1,230 @@
 use crate::{
-    hittable::{bvh::BvhNode, hittable_list::HittableList, instances::flats::Tria, Hittable},
-    materials::lambertian::Lambertian,
-    textures::image_texture::ImageTexture,
-    util::vec3::*,
+    features::LoadError,
+    hittable::{
+        bvh::{BvhLayout, BvhStrategy},
+        instances::triangle_mesh::{MeshBuffers, Submesh, TriangleMesh},
+        HitRecord, Hittable,
+    },
+    util::{aabb::Aabb, degrees_to_radians, interval::Interval, ray::Ray, vec3::*},
+    worlds::scene_file::desc::{ObjectDesc, ShapeDesc},
 };
-use std::ops::Deref;
 use std::path::Path;
 use tobj;
 
+mod mtl;
+
 pub struct LoadParam<'a> {
-    pub obj_file: &'a str,
+    pub file: &'a str,
     pub zoom: f64,
     pub offset: Vec3,
     pub rot_x: f64,
     pub rot_y: f64, //degree
     pub rot_z: f64,
+    pub bvh: BvhStrategy,
+    pub layout: BvhLayout,
+    // only used when the file has no normals: flat faces instead of
+    // normals averaged over the faces around each vertex
+    pub flat_normals: bool,
 }
 
-pub fn obj_mesh(param: LoadParam) -> Box<dyn Hittable> {
-    let the_obj = tobj::load_obj(param.obj_file, &tobj::GPU_LOAD_OPTIONS);
-    assert!(the_obj.is_ok());
-    let (models, materials) = the_obj.unwrap();
-    let materials = materials.unwrap();
-    let dir = Path::new(param.obj_file).parent();
-    let dir = if let Some(par) = dir {
-        let mut par = String::from(par.to_str().unwrap());
-        if !par.ends_with('/') {
-            par.push('/');
-        }
-        par
-    } else {
-        String::from("")
+// a loaded model, remembering where it came from so it can be described again
+pub struct ObjMesh {
+    mesh: TriangleMesh,
+    obj_file: String,
+    zoom: f64,
+    offset: Vec3,
+    rot: [f64; 3],
+    flat_normals: bool,
+}
+
+impl Hittable for ObjMesh {
+    fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
+        self.mesh.hit(r, ray_t, rec)
+    }
+    fn bounding_box(&self) -> &Aabb {
+        self.mesh.bounding_box()
+    }
+    fn describe(&self) -> Option<ObjectDesc> {
+        Some(
+            ShapeDesc::Obj {
+                file: self.obj_file.clone(),
+                zoom: self.zoom,
+                offset: self.offset.e,
+                rot_x: self.rot[0],
+                rot_y: self.rot[1],
+                rot_z: self.rot[2],
+                flat_normals: self.flat_normals,
+            }
+            .into(),
+        )
+    }
+}
+
+pub fn obj_mesh(param: LoadParam) -> Result<Box<dyn Hittable>, LoadError> {
+    let error = |msg: String| LoadError {
+        file: String::from(param.file),
+        msg,
     };
-    let dir = dir.deref();
-    let mut trias = Vec::<Tria<_>>::new();
+    let (models, materials) = tobj::load_obj(param.file, &tobj::GPU_LOAD_OPTIONS)
+        .map_err(|err| error(format!("cannot load obj: {}", err)))?;
+    // a broken or missing .mtl still leaves a usable model
+    let materials = materials.unwrap_or_else(|err| {
+        eprintln!(
+            "{}: cannot load materials ({}), using a default one",
+            param.file, err
+        );
+        Vec::new()
+    });
+    let dir = Path::new(param.file).parent().unwrap_or(Path::new(""));
+    let mut mats = Vec::with_capacity(materials.len());
+    for mtl in materials.iter() {
+        mats.push(mtl::translate(mtl, dir).map_err(error)?);
+    }
+    let default_mat = mtl::default_material();
 
-    for (_, md) in models.iter().enumerate() {
-        let mesh = &md.mesh;
-        let mut points = Vec::<_>::new();
-        let mut texs = Vec::<_>::new();
-        let mut normals = Vec::<_>::new();
-        let mat_id = md.mesh.material_id.unwrap();
-        let mat_file =
-            String::from(dir) + materials[mat_id].diffuse_texture.clone().unwrap().as_str();
-        let mat_pic = ImageTexture::new_path(mat_file.as_str());
-        let mat = Lambertian::new_tex(mat_pic);
-        assert_eq!(mesh.positions.len() % 3, 0);
-        assert_ne!(mesh.texcoords.len(), 0);
-        assert_ne!(mesh.normals.len(), 0);
-        assert_ne!(mesh.indices.len(), 0);
+    // every model becomes a submesh of one mesh, models sharing a material
+    // share it here too instead of each loading the texture again
+    let mut positions = Vec::<Point3>::new();
+    let mut normals = Vec::<Vec3>::new();
+    let mut texs = Vec::<(f64, f64)>::new();
+    let mut submeshes = Vec::new();
 
-        for p in mesh.positions.chunks(3) {
-            points.push(Point3::new(p[0] as f64, p[1] as f64, p[2] as f64));
-        }
-        for t in mesh.texcoords.chunks(2) {
-            texs.push((t[0] as f64, t[1] as f64));
+    for md in models.iter() {
+        let mesh = &md.mesh;
+        if mesh.indices.is_empty() {
+            continue;
         }
-        for n in mesh.normals.chunks(3) {
-            normals.push(Vec3::new(n[0] as f64, n[1] as f64, n[2] as f64));
+        let count = mesh.positions.len() / 3;
+        if mesh.positions.len() % 3 != 0
+            || !(mesh.normals.is_empty() || mesh.normals.len() == 3 * count)
+            || !(mesh.texcoords.is_empty() || mesh.texcoords.len() == 2 * count)
+        {
+            return Err(error(format!("model '{}': malformed vertex data", md.name)));
         }
 
-        for id in mesh.indices.chunks(3) {
-            let mut tria = Tria::new(
-                &points,
-                &texs,
-                &normals,
-                [id[0] as usize, id[1] as usize, id[2] as usize],
-                mat.clone(),
-            );
-            tria.zoom(param.zoom);
-            tria.trans(param.offset);
-            trias.push(tria);
+        let mut model = MeshBuffers {
+            positions: mesh
+                .positions
+                .chunks(3)
+                .map(|p| Point3::new(p[0] as f64, p[1] as f64, p[2] as f64))
+                .collect(),
+            normals: mesh
+                .normals
+                .chunks(3)
+                .map(|n| Vec3::new(n[0] as f64, n[1] as f64, n[2] as f64))
+                .collect(),
+            texs: mesh
+                .texcoords
+                .chunks(2)
+                .map(|t| (t[0] as f64, t[1] as f64))
+                .collect(),
+            colors: Vec::new(),
+            indices: mesh
+                .indices
+                .chunks(3)
+                .map(|id| [id[0], id[1], id[2]])
+                .collect(),
+        };
+        if model.normals.is_empty() {
+            if param.flat_normals {
+                model.flat_normals();
+            } else {
+                model.smooth_normals();
+            }
+        }
+        if model.texs.is_empty() {
+            model.planar_uvs();
         }
+
+        let base = positions.len() as u32;
+        positions.extend(model.positions);
+        normals.extend(model.normals);
+        texs.extend(model.texs);
+        let (material, normal_map) = match mesh.material_id.and_then(|id| mats.get(id)) {
+            Some(mat) => (mat.material.clone(), mat.normal_map.clone()),
+            None => (default_mat.clone(), None),
+        };
+        submeshes.push(Submesh {
+            indices: model
+                .indices
+                .iter()
+                .map(|id| id.map(|i| base + i))
+                .collect(),
+            material,
+            normal_map,
+        });
     }
+    if submeshes.is_empty() {
+        return Err(error(String::from("no faces")));
+    }
+
+    place(
+        &param,
+        &mut positions,
+        &mut normals,
+        submeshes.iter().flat_map(|s| s.indices.iter()),
+    );
+
+    Ok(Box::new(ObjMesh {
+        mesh: TriangleMesh::new(
+            positions,
+            normals,
+            texs,
+            Vec::new(),
+            submeshes,
+            param.bvh,
+            param.layout,
+        ),
+        obj_file: String::from(param.file),
+        zoom: param.zoom,
+        offset: param.offset,
+        rot: [param.rot_x, param.rot_y, param.rot_z],
+        flat_normals: param.flat_normals,
+    }))
+}
 
-    let mut world = HittableList::default();
+// scale and move the model, then rotate it around the center of all face
+// corners; shared by every format LoadParam places
+pub fn place<'a>(
+    param: &LoadParam,
+    positions: &mut [Point3],
+    normals: &mut [Vec3],
+    faces: impl Iterator<Item = &'a [u32; 3]> + Clone,
+) {
+    for p in positions.iter_mut() {
+        *p = *p * param.zoom + param.offset;
+    }
     let mut center_old = Vec3::default();
-    let tot_points: f64 = 3.0 * trias.len() as f64;
-    for t in trias.iter() {
-        for i in 0..3 {
-            center_old.e[0] += t.p[i].e[0] / tot_points;
-            center_old.e[1] += t.p[i].e[1] / tot_points;
-            center_old.e[2] += t.p[i].e[2] / tot_points;
-        }
+    let tot_points = 3.0 * faces.clone().count() as f64;
+    for id in faces.flatten() {
+        center_old += positions[*id as usize] / tot_points;
     }
-    for t in trias {
-        let mut r_tri = t;
-        r_tri.rotate(center_old, param.rot_x, param.rot_y, param.rot_z);
-        world.add(Box::new(r_tri));
+    for p in positions.iter_mut() {
+        *p = rotate(*p - center_old, param.rot_x, param.rot_y, param.rot_z) + center_old;
     }
-    Box::new(BvhNode::new_list(world))
+    for n in normals.iter_mut() {
+        *n = rotate(*n, param.rot_x, param.rot_y, param.rot_z);
+    }
+}
+
+// degrees around x, then y, then z
+fn rotate(v: Vec3, r_x: f64, r_y: f64, r_z: f64) -> Vec3 {
+    let (sin_x, cos_x) = degrees_to_radians(r_x).sin_cos();
+    let v = Vec3::new(
+        v.x(),
+        cos_x * v.y() - sin_x * v.z(),
+        sin_x * v.y() + cos_x * v.z(),
+    );
+    let (sin_y, cos_y) = degrees_to_radians(r_y).sin_cos();
+    let v = Vec3::new(
+        cos_y * v.x() - sin_y * v.z(),
+        v.y(),
+        sin_y * v.x() + cos_y * v.z(),
+    );
+    let (sin_z, cos_z) = degrees_to_radians(r_z).sin_cos();
+    Vec3::new(
+        cos_z * v.x() - sin_z * v.y(),
+        sin_z * v.x() + cos_z * v.y(),
+        v.z(),
+    )
 }
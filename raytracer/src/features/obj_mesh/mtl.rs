@@ -0,0 +1,123 @@
+// turn .mtl materials into the crate's own: emissive ones become lights,
+// transparent ones glass, shiny ones metal, anything else lambertian.
+// a map_Kd wins over Kd, like most viewers do, so textured models keep
+// their look instead of being darkened by the usual Kd 0.64
+
+use crate::{
+    features::normal_map::{MapMap, NormalMap},
+    materials::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal,
+        Material,
+    },
+    textures::{image_texture::ImageTexture, SolidColor},
+    util::color::Color,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// the .mtl default for Kd
+const DEFAULT_KD: f64 = 0.8;
+const DEFAULT_GLASS_IOR: f64 = 1.5;
+
+pub struct Translated {
+    pub material: Arc<dyn Material>,
+    pub normal_map: Option<Arc<dyn NormalMap>>,
+}
+
+pub fn default_material() -> Arc<dyn Material> {
+    Arc::new(Lambertian::<SolidColor>::new_color(Color::new(
+        DEFAULT_KD, DEFAULT_KD, DEFAULT_KD,
+    )))
+}
+
+// texture paths are relative to the .mtl, i.e. to `dir`
+pub fn translate(mtl: &tobj::Material, dir: &Path) -> Result<Translated, String> {
+    let texture = |map: &Option<String>| -> Result<Option<String>, String> {
+        match map {
+            Some(map) => texture_path(dir, map)
+                .map(Some)
+                .ok_or_else(|| format!("material '{}': cannot find texture '{}'", mtl.name, map)),
+            None => Ok(None),
+        }
+    };
+    let illum = mtl.illumination_model.unwrap_or(2);
+    let kd = color(mtl.diffuse).unwrap_or(Color::new(DEFAULT_KD, DEFAULT_KD, DEFAULT_KD));
+    let ks = color(mtl.specular).unwrap_or_default();
+    let ke = match mtl.unknown_param.get("Ke") {
+        Some(ke) => {
+            parse_color(ke).ok_or_else(|| format!("material '{}': bad Ke '{}'", mtl.name, ke))?
+        }
+        None => Color::default(),
+    };
+
+    let material: Arc<dyn Material> = if max(&ke) > 0.0 {
+        Arc::new(DiffuseLight::<SolidColor>::new_color(ke))
+    } else if mtl.dissolve.map_or(false, |d| d < 1.0) || matches!(illum, 4 | 6 | 7 | 9) {
+        // Ni 1 is what exporters write when they mean "not set"
+        let ior = match mtl.optical_density {
+            Some(ni) if ni > 1.0 => ni as f64,
+            _ => DEFAULT_GLASS_IOR,
+        };
+        Arc::new(Dielectric::new(ior))
+    } else if matches!(illum, 3 | 5 | 8) || max(&ks) > max(&kd) {
+        // a Phong exponent turned into a fuzz radius: Ns 0 is fully rough,
+        // the usual 1000 nearly a mirror
+        let ns = mtl.shininess.unwrap_or(0.0).max(0.0) as f64;
+        let fuzz = (2.0 / (ns + 2.0)).sqrt();
+        match texture(&mtl.specular_texture)? {
+            Some(path) => Arc::new(Metal::new_tex(ImageTexture::new_path(&path), fuzz)),
+            None => Arc::new(Metal::<SolidColor>::new_color(ks, fuzz)),
+        }
+    } else {
+        match texture(&mtl.diffuse_texture)? {
+            Some(path) => Arc::new(Lambertian::new_tex(ImageTexture::new_path(&path))),
+            None => Arc::new(Lambertian::<SolidColor>::new_color(kd)),
+        }
+    };
+
+    // map_Bump is meant as a height map, but the crate only knows normal
+    // maps and that is what most models ship under the name anyway
+    let normal_map = texture(&mtl.normal_texture)?
+        .map(|path| Arc::new(MapMap::new(&path)) as Arc<dyn NormalMap>);
+
+    Ok(Translated {
+        material,
+        normal_map,
+    })
+}
+
+// maps may carry options, e.g. "-bm 0.5 bump.png", the file is the last word
+fn texture_path(dir: &Path, map: &str) -> Option<String> {
+    let map = map.trim();
+    let file = if map.starts_with('-') {
+        map.split_whitespace().last()?
+    } else {
+        map
+    };
+    let candidates = [PathBuf::from(file), PathBuf::from(file.replace('\\', "/"))];
+    candidates
+        .iter()
+        .map(|file| dir.join(file))
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+fn color(c: Option<[f32; 3]>) -> Option<Color> {
+    c.map(|c| Color::new(c[0] as f64, c[1] as f64, c[2] as f64))
+}
+
+fn parse_color(s: &str) -> Option<Color> {
+    let c: Vec<f64> = s
+        .split_whitespace()
+        .map(|x| x.parse().ok())
+        .collect::<Option<_>>()?;
+    match c[..] {
+        [r, g, b] => Some(Color::new(r, g, b)),
+        [v] => Some(Color::new(v, v, v)),
+        _ => None,
+    }
+}
+
+fn max(c: &Color) -> f64 {
+    c.x().max(c.y()).max(c.z())
+}
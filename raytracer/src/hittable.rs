@@ -5,8 +5,10 @@ pub mod transforms;
 
 use crate::{
     materials::Material,
-    util::{aabb::Aabb, interval::Interval, ray::Ray, vec3::*},
+    util::{aabb::Aabb, color::Color, interval::Interval, ray::Ray, vec3::*},
+    worlds::scene_file::desc::ObjectDesc,
 };
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct HitRecord<'a> {
@@ -17,6 +19,9 @@ pub struct HitRecord<'a> {
     pub u: f64,
     pub v: f64,
     pub front_face: bool,
+    // vertex colors of a mesh interpolated at the hit, white elsewhere;
+    // scales the albedo of the material
+    pub color: Color,
 }
 
 impl<'a> HitRecord<'a> {
@@ -39,4 +44,46 @@ pub trait Hittable: Send + Sync {
     fn random(&self, _origin: &Point3) -> Vec3 {
         Vec3::new(1.0, 0.0, 0.0)
     }
+    // how to write this object into a scene file, None if it cannot be
+    fn describe(&self) -> Option<ObjectDesc> {
+        None
+    }
+}
+
+// lets boxed objects be wrapped by transforms and media
+impl<H: Hittable + ?Sized> Hittable for Box<H> {
+    fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
+        (**self).hit(r, ray_t, rec)
+    }
+    fn bounding_box(&self) -> &Aabb {
+        (**self).bounding_box()
+    }
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+    fn random(&self, origin: &Point3) -> Vec3 {
+        (**self).random(origin)
+    }
+    fn describe(&self) -> Option<ObjectDesc> {
+        (**self).describe()
+    }
+}
+
+// shared geometry, see transforms::instance
+impl<H: Hittable + ?Sized> Hittable for Arc<H> {
+    fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
+        (**self).hit(r, ray_t, rec)
+    }
+    fn bounding_box(&self) -> &Aabb {
+        (**self).bounding_box()
+    }
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+    fn random(&self, origin: &Point3) -> Vec3 {
+        (**self).random(origin)
+    }
+    fn describe(&self) -> Option<ObjectDesc> {
+        (**self).describe()
+    }
 }
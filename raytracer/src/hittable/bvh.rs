@@ -1,11 +1,259 @@
 // Bounding Volume Hierarchy, a big set of all hittables
 
+pub mod linear;
+pub mod sah;
+pub mod tree;
+
 use crate::{
     hittable::{hittable_list::HittableList, HitRecord, Hittable},
-    util::{aabb::Aabb, interval::Interval, ray::Ray},
+    util::{aabb::Aabb, interval::Interval, random::rng, ray::Ray},
+    worlds::scene_file::desc::{ObjectDesc, ShapeDesc},
 };
+use linear::LinearBvh;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// how a node splits its objects between the two children
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BvhStrategy {
+    // sort along a random axis and cut in the middle, fast to build
+    #[default]
+    Median,
+    // binned surface area heuristic, slower to build but cheaper to traverse
+    Sah,
+}
+
+impl FromStr for BvhStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "median" => Ok(BvhStrategy::Median),
+            "sah" => Ok(BvhStrategy::Sah),
+            _ => Err(format!("unknown bvh strategy '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for BvhStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BvhStrategy::Median => write!(f, "median"),
+            BvhStrategy::Sah => write!(f, "sah"),
+        }
+    }
+}
+
+// how the built tree is laid out in memory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BvhLayout {
+    // boxed nodes calling each other through Hittable
+    Tree,
+    // nodes in one array, walked with a stack, see linear.rs
+    #[default]
+    Linear,
+}
+
+impl FromStr for BvhLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "tree" => Ok(BvhLayout::Tree),
+            "linear" => Ok(BvhLayout::Linear),
+            _ => Err(format!("unknown bvh layout '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for BvhLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BvhLayout::Tree => write!(f, "tree"),
+            BvhLayout::Linear => write!(f, "linear"),
+        }
+    }
+}
+
+// what one build produced; `sah_cost` is the expected cost of a ray through
+// the tree (node visits + primitive tests, relative to hitting the root), the
+// usual measure of traversal quality
+#[derive(Clone, Debug, Default)]
+pub struct BvhStats {
+    pub strategy: BvhStrategy,
+    pub layout: BvhLayout,
+    pub primitives: usize,
+    pub nodes: usize,
+    pub max_depth: usize,
+    pub build_time: Duration,
+    pub sah_cost: f64,
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {} primitives, {} nodes, depth {}, built in {:.2}ms, SAH cost {:.2}",
+            self.strategy,
+            self.layout,
+            self.primitives,
+            self.nodes,
+            self.max_depth,
+            self.build_time.as_secs_f64() * 1000.0,
+            self.sah_cost
+        )
+    }
+}
+
+// every build is logged here, so the stats of trees built deep inside the
+// scene builders (e.g. for obj meshes) can still be reported
+static BUILD_STATS: Mutex<Vec<BvhStats>> = Mutex::new(Vec::new());
+
+pub fn take_build_stats() -> Vec<BvhStats> {
+    std::mem::take(&mut *BUILD_STATS.lock().unwrap())
+}
+
+// anything a bvh can be built over: scene objects, or the triangles of a mesh
+pub trait Bounded {
+    fn bounding_box(&self) -> &Aabb;
+}
+
+impl Bounded for Box<dyn Hittable> {
+    fn bounding_box(&self) -> &Aabb {
+        Hittable::bounding_box(&**self)
+    }
+}
+
+// surface areas summed during the build, turned into the SAH cost at the end
+#[derive(Default)]
+struct BuildCost {
+    nodes: f64,
+    primitives: f64,
+}
+
+// what both layouts share while building: the split rule and the bookkeeping
+struct Builder {
+    strategy: BvhStrategy,
+    stats: BvhStats,
+    cost: BuildCost,
+    start: Instant,
+}
+
+impl Builder {
+    fn new(strategy: BvhStrategy, layout: BvhLayout, primitives: usize) -> Self {
+        Builder {
+            strategy,
+            stats: BvhStats {
+                strategy,
+                layout,
+                primitives,
+                ..Default::default()
+            },
+            cost: BuildCost::default(),
+            start: Instant::now(),
+        }
+    }
+
+    // bounds of a new node at `depth`, counted into the stats
+    fn node<T: Bounded>(&mut self, objects: &[T], depth: usize) -> Aabb {
+        let mut bbox = Aabb::EMPTY;
+        for object in objects.iter() {
+            bbox = Aabb::new_aabb(&bbox, object.bounding_box());
+        }
+        self.stats.nodes += 1;
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        self.cost.nodes += bbox.surface_area();
+        bbox
+    }
+
+    fn leaf(&mut self, bbox: &Aabb, count: usize) {
+        self.cost.primitives += bbox.surface_area() * count as f64;
+    }
+
+    // leaves the left part in `objects`, returns the split axis and the right part
+    fn split<T: Bounded>(&self, objects: &mut Vec<T>) -> (i32, Vec<T>) {
+        match self.strategy {
+            BvhStrategy::Median => {
+                // use random axis directly
+                let axis = rng().gen_range(0..3);
+                objects.sort_by(|a, b| box_compare(a, b, axis));
+                let mid = objects.len() / 2;
+                (axis, objects.split_off(mid))
+            }
+            BvhStrategy::Sah => sah::split(objects),
+        }
+    }
+
+    fn finish(mut self, root: &Aabb) {
+        self.stats.build_time = self.start.elapsed();
+        let root_area = root.surface_area();
+        if root_area > 0.0 {
+            self.stats.sah_cost = (self.cost.nodes + self.cost.primitives) / root_area;
+        }
+        BUILD_STATS.lock().unwrap().push(self.stats);
+    }
+}
+
+// build in whichever layout was asked for
+pub fn build_bvh(
+    objects: Vec<Box<dyn Hittable>>,
+    strategy: BvhStrategy,
+    layout: BvhLayout,
+) -> Box<dyn Hittable> {
+    match layout {
+        BvhLayout::Tree => Box::new(BvhNode::build(objects, strategy)),
+        BvhLayout::Linear => Box::new(LinearBvh::build(objects, strategy)),
+    }
+}
+
+// a bvh over primitives that are not Hittable (mesh faces), in either layout
+pub enum PrimitiveBvh<P> {
+    Tree(tree::TreeBvh<P>),
+    Linear(LinearBvh<P>),
+}
+
+impl<P: Bounded> PrimitiveBvh<P> {
+    pub fn build(objects: Vec<P>, strategy: BvhStrategy, layout: BvhLayout) -> PrimitiveBvh<P> {
+        match layout {
+            BvhLayout::Tree => PrimitiveBvh::Tree(tree::TreeBvh::build(objects, strategy)),
+            BvhLayout::Linear => PrimitiveBvh::Linear(LinearBvh::build(objects, strategy)),
+        }
+    }
+}
+
+impl<P> PrimitiveBvh<P> {
+    pub fn map<Q>(self, f: impl FnMut(P) -> Q) -> PrimitiveBvh<Q> {
+        match self {
+            PrimitiveBvh::Tree(bvh) => PrimitiveBvh::Tree(bvh.map(f)),
+            PrimitiveBvh::Linear(bvh) => PrimitiveBvh::Linear(bvh.map(f)),
+        }
+    }
+
+    pub fn bounds(&self) -> &Aabb {
+        match self {
+            PrimitiveBvh::Tree(bvh) => bvh.bounds(),
+            PrimitiveBvh::Linear(bvh) => bvh.bounds(),
+        }
+    }
+
+    pub fn traverse<'a>(
+        &'a self,
+        r: &Ray,
+        ray_t: &Interval,
+        hit: impl FnMut(&'a P, &Interval) -> Option<f64>,
+    ) -> bool {
+        match self {
+            PrimitiveBvh::Tree(bvh) => bvh.traverse(r, ray_t, hit),
+            PrimitiveBvh::Linear(bvh) => bvh.traverse(r, ray_t, hit),
+        }
+    }
+}
 
 pub struct BvhNode {
     left: Option<Box<dyn Hittable>>,
@@ -14,48 +262,61 @@ pub struct BvhNode {
 }
 
 impl BvhNode {
-    pub fn new_vec(mut objects: Vec<Box<dyn Hittable>>) -> BvhNode {
-        let mut bbox = Aabb::EMPTY;
-        for object in objects.iter() {
-            bbox = Aabb::new_aabb(&bbox, object.bounding_box());
-        }
-        // use random axis directly
-        let axis = rand::thread_rng().gen_range(0..3);
+    pub fn new_vec(objects: Vec<Box<dyn Hittable>>) -> BvhNode {
+        Self::build(objects, BvhStrategy::Median)
+    }
+
+    pub fn new_list(list: HittableList) -> BvhNode {
+        Self::new_vec(list.objects)
+    }
+
+    pub fn build_list(list: HittableList, strategy: BvhStrategy) -> BvhNode {
+        Self::build(list.objects, strategy)
+    }
+
+    pub fn build(objects: Vec<Box<dyn Hittable>>, strategy: BvhStrategy) -> BvhNode {
+        let mut builder = Builder::new(strategy, BvhLayout::Tree, objects.len());
+        let root = Self::build_node(objects, 1, &mut builder);
+        builder.finish(&root.bbox);
+        root
+    }
+
+    fn build_node(
+        mut objects: Vec<Box<dyn Hittable>>,
+        depth: usize,
+        builder: &mut Builder,
+    ) -> BvhNode {
+        let bbox = builder.node(&objects, depth);
         // bisect recursion
         let object_span = objects.len();
 
-        let (left, right) = if object_span == 1 {
-            (Some(objects.pop().unwrap()), None)
-        } else if object_span == 2 {
-            let r = objects.pop().unwrap();
-            let l = objects.pop().unwrap();
-            (Some(l), Some(r))
+        let (left, right) = if object_span <= 2 {
+            builder.leaf(&bbox, object_span);
+            let r = if object_span == 2 {
+                objects.pop()
+            } else {
+                None
+            };
+            (objects.pop(), r)
         } else {
-            objects.sort_by(|a, b| Self::box_compare(&**a, &**b, axis));
-            let mid = object_span / 2;
-            let mut left_vec = objects;
-            let right_vec = left_vec.split_off(mid);
-            (
-                Some(Box::new(BvhNode::new_vec(left_vec)) as Box<dyn Hittable>),
-                Some(Box::new(BvhNode::new_vec(right_vec)) as Box<dyn Hittable>),
-            )
+            let (_, right_vec) = builder.split(&mut objects);
+            let mut child = |objects| {
+                Box::new(Self::build_node(objects, depth + 1, builder)) as Box<dyn Hittable>
+            };
+            (Some(child(objects)), Some(child(right_vec)))
         };
 
         BvhNode { left, right, bbox }
     }
+}
 
-    pub fn new_list(list: HittableList) -> BvhNode {
-        Self::new_vec(list.objects)
-    }
-
-    fn box_compare(a: &dyn Hittable, b: &dyn Hittable, axis_index: i32) -> Ordering {
-        let a_axis_interval = a.bounding_box().axis_interval(axis_index);
-        let b_axis_interval = b.bounding_box().axis_interval(axis_index);
-        a_axis_interval
-            .min
-            .partial_cmp(&b_axis_interval.min)
-            .unwrap_or(Ordering::Equal)
-    }
+fn box_compare<T: Bounded>(a: &T, b: &T, axis_index: i32) -> Ordering {
+    let a_axis_interval = a.bounding_box().axis_interval(axis_index);
+    let b_axis_interval = b.bounding_box().axis_interval(axis_index);
+    a_axis_interval
+        .min
+        .partial_cmp(&b_axis_interval.min)
+        .unwrap_or(Ordering::Equal)
 }
 
 impl Hittable for BvhNode {
@@ -85,4 +346,30 @@ impl Hittable for BvhNode {
     fn bounding_box(&self) -> &Aabb {
         &self.bbox
     }
+    fn describe(&self) -> Option<ObjectDesc> {
+        // the tree is rebuilt on load, so only the leaves are kept
+        describe_leaves([&self.left, &self.right].into_iter().flatten())
+    }
+}
+
+// nested bvhs are merged into one list of leaves
+fn describe_leaves<'a>(
+    children: impl Iterator<Item = &'a Box<dyn Hittable>>,
+) -> Option<ObjectDesc> {
+    let mut objects = Vec::new();
+    for child in children {
+        match child.describe()? {
+            ObjectDesc {
+                rotate_y: None,
+                translate: None,
+                matrix: None,
+                motion,
+                material_override: None,
+                shape: ShapeDesc::Bvh { objects: leaves },
+                ..
+            } if motion.is_empty() => objects.extend(leaves),
+            desc => objects.push(desc),
+        }
+    }
+    Some(ShapeDesc::Bvh { objects }.into())
 }
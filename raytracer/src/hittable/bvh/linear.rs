@@ -0,0 +1,155 @@
+// the same hierarchy as BvhNode, but flattened into one array in depth-first
+// order: the first child of a node is always the next entry, so only the
+// second one needs an index. walking it takes a loop and a small stack
+// instead of a virtual call per node, and the child the ray reaches first
+// is visited first, so the far one is often culled by the closer hit
+
+use super::{describe_leaves, Bounded, Builder, BvhLayout, BvhStrategy};
+use crate::{
+    hittable::{HitRecord, Hittable},
+    util::{aabb::Aabb, interval::Interval, ray::Ray},
+    worlds::scene_file::desc::ObjectDesc,
+};
+
+// deeper than this the rest goes into one leaf, keeps the traversal stack fixed
+const MAX_DEPTH: usize = 64;
+
+struct LinearNode {
+    bbox: Aabb,
+    // a leaf: its first primitive; otherwise the index of the second child
+    offset: u32,
+    // primitives in a leaf, 0 marks an inner node
+    count: u32,
+    // the children were split along this axis, left one on the low side
+    axis: u8,
+}
+
+// built over scene objects by default, a mesh builds one over its triangles
+pub struct LinearBvh<P = Box<dyn Hittable>> {
+    nodes: Vec<LinearNode>,
+    primitives: Vec<P>,
+}
+
+impl<P: Bounded> LinearBvh<P> {
+    pub fn build(objects: Vec<P>, strategy: BvhStrategy) -> LinearBvh<P> {
+        let mut builder = Builder::new(strategy, BvhLayout::Linear, objects.len());
+        let mut ret = LinearBvh {
+            nodes: Vec::with_capacity(2 * objects.len().max(1)),
+            primitives: Vec::with_capacity(objects.len()),
+        };
+        ret.build_node(objects, 1, &mut builder);
+        builder.finish(&ret.nodes[0].bbox);
+        ret
+    }
+
+    fn build_node(&mut self, mut objects: Vec<P>, depth: usize, builder: &mut Builder) {
+        let bbox = builder.node(&objects, depth);
+        let index = self.nodes.len();
+
+        if objects.len() <= 2 || depth >= MAX_DEPTH {
+            builder.leaf(&bbox, objects.len());
+            self.nodes.push(LinearNode {
+                bbox,
+                offset: self.primitives.len() as u32,
+                count: objects.len() as u32,
+                axis: 0,
+            });
+            self.primitives.append(&mut objects);
+            return;
+        }
+
+        let (axis, right) = builder.split(&mut objects);
+        self.nodes.push(LinearNode {
+            bbox,
+            offset: 0,
+            count: 0,
+            axis: axis as u8,
+        });
+        self.build_node(objects, depth + 1, builder);
+        self.nodes[index].offset = self.nodes.len() as u32;
+        self.build_node(right, depth + 1, builder);
+    }
+}
+
+impl<P> LinearBvh<P> {
+    // keep the tree, swap what the leaves hold (e.g. drop the boxes only
+    // needed while building)
+    pub fn map<Q>(self, f: impl FnMut(P) -> Q) -> LinearBvh<Q> {
+        LinearBvh {
+            nodes: self.nodes,
+            primitives: self.primitives.into_iter().map(f).collect(),
+        }
+    }
+
+    pub fn bounds(&self) -> &Aabb {
+        &self.nodes[0].bbox
+    }
+
+    // walk the nodes nearest first; `hit` tests one primitive within the
+    // interval and returns the distance if it was hit
+    pub fn traverse<'a>(
+        &'a self,
+        r: &Ray,
+        ray_t: &Interval,
+        mut hit: impl FnMut(&'a P, &Interval) -> Option<f64>,
+    ) -> bool {
+        let dir = r.direction();
+        let dir_is_neg = [dir.x() < 0.0, dir.y() < 0.0, dir.z() < 0.0];
+        let mut closest = ray_t.max;
+        let mut hit_anything = false;
+
+        let mut stack = [0u32; MAX_DEPTH];
+        let mut top = 0;
+        let mut current = 0;
+        loop {
+            let node = &self.nodes[current];
+            if node.bbox.hit(r, &Interval::new(ray_t.min, closest)) {
+                if node.count > 0 {
+                    let first = node.offset as usize;
+                    for object in &self.primitives[first..first + node.count as usize] {
+                        if let Some(t) = hit(object, &Interval::new(ray_t.min, closest)) {
+                            hit_anything = true;
+                            closest = t;
+                        }
+                    }
+                } else {
+                    // going against the axis the right child comes first
+                    let (near, far) = if dir_is_neg[node.axis as usize] {
+                        (node.offset as usize, current + 1)
+                    } else {
+                        (current + 1, node.offset as usize)
+                    };
+                    stack[top] = far as u32;
+                    top += 1;
+                    current = near;
+                    continue;
+                }
+            }
+            if top == 0 {
+                break;
+            }
+            top -= 1;
+            current = stack[top] as usize;
+        }
+        hit_anything
+    }
+}
+
+impl Hittable for LinearBvh {
+    fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
+        self.traverse(r, ray_t, |object, interval| {
+            if object.hit(r, interval, rec) {
+                rec.as_ref().map(|rec_data| rec_data.t)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        self.bounds()
+    }
+    fn describe(&self) -> Option<ObjectDesc> {
+        describe_leaves(self.primitives.iter())
+    }
+}
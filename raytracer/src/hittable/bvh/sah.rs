@@ -0,0 +1,102 @@
+// binned surface area heuristic: the centroids are dropped into a few bins
+// per axis and the cut between two bins with the lowest
+//   area(left) * count(left) + area(right) * count(right)
+// wins, i.e. the split a random ray is expected to pay least for
+
+use super::Bounded;
+use crate::util::aabb::Aabb;
+
+const BIN_COUNT: usize = 16;
+
+#[derive(Clone)]
+struct Bin {
+    bbox: Aabb,
+    count: usize,
+}
+
+// leaves the left part in `objects` and returns the split axis and the right one
+pub fn split<T: Bounded>(objects: &mut Vec<T>) -> (i32, Vec<T>) {
+    let mut best: Option<(f64, i32, usize)> = None;
+    let mut best_bounds = (0.0, 0.0);
+
+    for axis in 0..3 {
+        let (min, max) = centroid_bounds(objects, axis);
+        if max - min <= 0.0 {
+            continue;
+        }
+        let mut bins = vec![
+            Bin {
+                bbox: Aabb::EMPTY,
+                count: 0,
+            };
+            BIN_COUNT
+        ];
+        for object in objects.iter() {
+            let b = &mut bins[bin_index(object.bounding_box().centroid(axis), min, max)];
+            b.bbox = Aabb::new_aabb(&b.bbox, object.bounding_box());
+            b.count += 1;
+        }
+
+        // sweep from the right to know the cost of every right part, then
+        // from the left to combine them
+        let mut right_cost = vec![0.0; BIN_COUNT];
+        let (mut right_box, mut right_count) = (Aabb::EMPTY, 0);
+        for i in (1..BIN_COUNT).rev() {
+            right_box = Aabb::new_aabb(&right_box, &bins[i].bbox);
+            right_count += bins[i].count;
+            right_cost[i] = area(&right_box, right_count) * right_count as f64;
+        }
+        let (mut left_box, mut left_count) = (Aabb::EMPTY, 0);
+        for i in 0..BIN_COUNT - 1 {
+            left_box = Aabb::new_aabb(&left_box, &bins[i].bbox);
+            left_count += bins[i].count;
+            let cost = area(&left_box, left_count) * left_count as f64 + right_cost[i + 1];
+            if left_count > 0
+                && left_count < objects.len()
+                && best.map_or(true, |(c, _, _)| cost < c)
+            {
+                best = Some((cost, axis, i));
+                best_bounds = (min, max);
+            }
+        }
+    }
+
+    match best {
+        Some((_, axis, cut)) => {
+            let (min, max) = best_bounds;
+            let (left, right): (Vec<_>, Vec<_>) = objects
+                .drain(..)
+                .partition(|o| bin_index(o.bounding_box().centroid(axis), min, max) <= cut);
+            *objects = left;
+            (axis, right)
+        }
+        // all centroids in one spot, any split is as good as another
+        None => {
+            let mid = objects.len() / 2;
+            (0, objects.split_off(mid))
+        }
+    }
+}
+
+fn centroid_bounds<T: Bounded>(objects: &[T], axis: i32) -> (f64, f64) {
+    objects
+        .iter()
+        .map(|o| o.bounding_box().centroid(axis))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), c| {
+            (lo.min(c), hi.max(c))
+        })
+}
+
+fn bin_index(c: f64, min: f64, max: f64) -> usize {
+    let i = ((c - min) / (max - min) * BIN_COUNT as f64) as usize;
+    i.min(BIN_COUNT - 1)
+}
+
+// empty boxes have infinite extents, but they also hold nothing
+fn area(bbox: &Aabb, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        bbox.surface_area()
+    }
+}
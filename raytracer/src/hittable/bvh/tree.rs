@@ -0,0 +1,123 @@
+// the BvhNode layout for primitives that are not Hittable themselves (the
+// faces of a mesh): boxed nodes, leaves of up to two, visited left then right
+
+use super::{Bounded, Builder, BvhLayout, BvhStrategy};
+use crate::util::{aabb::Aabb, interval::Interval, ray::Ray};
+
+enum TreeNode<P> {
+    Leaf {
+        bbox: Aabb,
+        primitives: Vec<P>,
+    },
+    Inner {
+        bbox: Aabb,
+        left: Box<TreeNode<P>>,
+        right: Box<TreeNode<P>>,
+    },
+}
+
+pub struct TreeBvh<P> {
+    root: TreeNode<P>,
+}
+
+impl<P: Bounded> TreeBvh<P> {
+    pub fn build(objects: Vec<P>, strategy: BvhStrategy) -> TreeBvh<P> {
+        let mut builder = Builder::new(strategy, BvhLayout::Tree, objects.len());
+        let root = Self::build_node(objects, 1, &mut builder);
+        builder.finish(root.bbox());
+        TreeBvh { root }
+    }
+
+    fn build_node(mut objects: Vec<P>, depth: usize, builder: &mut Builder) -> TreeNode<P> {
+        let bbox = builder.node(&objects, depth);
+        if objects.len() <= 2 {
+            builder.leaf(&bbox, objects.len());
+            return TreeNode::Leaf {
+                bbox,
+                primitives: objects,
+            };
+        }
+        let (_, right) = builder.split(&mut objects);
+        TreeNode::Inner {
+            bbox,
+            left: Box::new(Self::build_node(objects, depth + 1, builder)),
+            right: Box::new(Self::build_node(right, depth + 1, builder)),
+        }
+    }
+}
+
+impl<P> TreeNode<P> {
+    fn bbox(&self) -> &Aabb {
+        match self {
+            TreeNode::Leaf { bbox, .. } | TreeNode::Inner { bbox, .. } => bbox,
+        }
+    }
+
+    fn map<Q>(self, f: &mut impl FnMut(P) -> Q) -> TreeNode<Q> {
+        match self {
+            TreeNode::Leaf { bbox, primitives } => TreeNode::Leaf {
+                bbox,
+                primitives: primitives.into_iter().map(f).collect(),
+            },
+            TreeNode::Inner { bbox, left, right } => TreeNode::Inner {
+                bbox,
+                left: Box::new(left.map(f)),
+                right: Box::new(right.map(f)),
+            },
+        }
+    }
+
+    // returns the closest distance hit, a hit on the left shortens the
+    // interval the right side is tested in
+    fn traverse<'a>(
+        &'a self,
+        r: &Ray,
+        ray_t: &Interval,
+        hit: &mut impl FnMut(&'a P, &Interval) -> Option<f64>,
+    ) -> Option<f64> {
+        if !self.bbox().hit(r, ray_t) {
+            return None;
+        }
+        match self {
+            TreeNode::Leaf { primitives, .. } => {
+                let mut closest = None;
+                for object in primitives {
+                    let max = closest.unwrap_or(ray_t.max);
+                    if let Some(t) = hit(object, &Interval::new(ray_t.min, max)) {
+                        closest = Some(t);
+                    }
+                }
+                closest
+            }
+            TreeNode::Inner { left, right, .. } => {
+                let left_t = left.traverse(r, ray_t, hit);
+                let max = left_t.unwrap_or(ray_t.max);
+                right
+                    .traverse(r, &Interval::new(ray_t.min, max), hit)
+                    .or(left_t)
+            }
+        }
+    }
+}
+
+impl<P> TreeBvh<P> {
+    pub fn map<Q>(self, mut f: impl FnMut(P) -> Q) -> TreeBvh<Q> {
+        TreeBvh {
+            root: self.root.map(&mut f),
+        }
+    }
+
+    pub fn bounds(&self) -> &Aabb {
+        self.root.bbox()
+    }
+
+    // same contract as LinearBvh::traverse
+    pub fn traverse<'a>(
+        &'a self,
+        r: &Ray,
+        ray_t: &Interval,
+        mut hit: impl FnMut(&'a P, &Interval) -> Option<f64>,
+    ) -> bool {
+        self.root.traverse(r, ray_t, &mut hit).is_some()
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     hittable::{HitRecord, Hittable},
-    util::{aabb::Aabb, interval::Interval, ray::Ray, vec3::*},
+    util::{aabb::Aabb, interval::Interval, random::rng, ray::Ray, vec3::*},
+    worlds::scene_file::desc::{ObjectDesc, ShapeDesc},
 };
 use rand::Rng;
 
@@ -57,9 +58,17 @@ impl Hittable for HittableList {
         if self.objects.is_empty() {
             return Vec3::random();
         }
-        let mut rng = rand::thread_rng();
+        let mut rng = rng();
         let int_size = self.objects.len();
         let pos = rng.gen_range(0..int_size);
         self.objects[pos].random(origin)
     }
+    fn describe(&self) -> Option<ObjectDesc> {
+        let objects = self
+            .objects
+            .iter()
+            .map(|object| object.describe())
+            .collect::<Option<Vec<_>>>()?;
+        Some(ShapeDesc::List { objects }.into())
+    }
 }
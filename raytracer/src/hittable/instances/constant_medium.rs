@@ -3,9 +3,11 @@ use crate::materials::isotropic::Isotropic;
 use crate::util::aabb::Aabb;
 use crate::util::color::Color;
 use crate::util::interval::Interval;
+use crate::util::random::rng;
 use crate::util::ray::Ray;
 use crate::util::INFINITY;
-use crate::{hittable::Hittable, util::vec3::*};
+use crate::worlds::scene_file::desc::{MaterialDesc, ObjectDesc, ShapeDesc};
+use crate::{hittable::Hittable, materials::Material, util::vec3::*};
 use rand::Rng;
 
 pub struct ConstMedium<H: Hittable> {
@@ -26,7 +28,7 @@ impl<H: Hittable> ConstMedium<H> {
 
 impl<H: Hittable> Hittable for ConstMedium<H> {
     fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
-        let mut rng = rand::thread_rng();
+        let mut rng = rng();
         let enable_debug = false;
         let debugging = enable_debug && rng.gen_range(0.0..1.0) < 0.00001;
 
@@ -87,6 +89,7 @@ impl<H: Hittable> Hittable for ConstMedium<H> {
             u: 0.0,
             v: 0.0,
             front_face: true,
+            color: Color::new(1.0, 1.0, 1.0),
         });
 
         if debugging {
@@ -102,4 +105,17 @@ impl<H: Hittable> Hittable for ConstMedium<H> {
     fn bounding_box(&self) -> &Aabb {
         self.boundary.bounding_box()
     }
+    fn describe(&self) -> Option<ObjectDesc> {
+        let Some(MaterialDesc::Isotropic { albedo }) = self.phase_function.describe() else {
+            return None;
+        };
+        Some(
+            ShapeDesc::ConstantMedium {
+                density: -1.0 / self.neg_inv_density,
+                albedo,
+                boundary: Box::new(self.boundary.describe()?),
+            }
+            .into(),
+        )
+    }
 }
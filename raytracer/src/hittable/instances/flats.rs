@@ -2,10 +2,13 @@ use crate::features::normal_map::NormalMap;
 use crate::hittable::hittable_list::HittableList;
 use crate::hittable::{HitRecord, Hittable};
 use crate::util::aabb::Aabb;
+use crate::util::color::Color;
 use crate::util::interval::Interval;
 use crate::util::onb::Onb;
+use crate::util::random::rng;
 use crate::util::ray::Ray;
 use crate::util::{degrees_to_radians, INFINITY};
+use crate::worlds::scene_file::desc::{MaterialRef, ObjectDesc, ShapeDesc};
 use crate::{materials::Material, util::vec3::*};
 use rand::Rng;
 
@@ -90,6 +93,7 @@ impl<M: Material, N: NormalMap> Hittable for Quad<M, N> {
             u: alpha,
             v: beta,
             front_face: false,
+            color: Color::new(1.0, 1.0, 1.0),
         };
         rec_data.set_face_normal(r, self.normal);
         // 在此处修改normal，但愿有效
@@ -125,19 +129,72 @@ impl<M: Material, N: NormalMap> Hittable for Quad<M, N> {
     }
 
     fn random(&self, origin: &Point3) -> Vec3 {
-        let mut rng = rand::thread_rng();
+        let mut rng = rng();
         let p = self.q + (self.u * rng.gen_range(0.0..1.0)) + (self.v * rng.gen_range(0.0..1.0));
         &p - origin
     }
+    fn describe(&self) -> Option<ObjectDesc> {
+        Some(
+            ShapeDesc::Quad {
+                q: self.q.e,
+                u: self.u.e,
+                v: self.v.e,
+                normal_map: self.nmap.describe(),
+                material: Some(MaterialRef::Inline(Box::new(self.mat.describe()?))),
+            }
+            .into(),
+        )
+    }
 }
 
 // a 3d box,formed by six sides
+pub struct Cube {
+    sides: HittableList,
+    a: Point3,
+    b: Point3,
+}
+
+impl Hittable for Cube {
+    fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
+        self.sides.hit(r, ray_t, rec)
+    }
+    fn bounding_box(&self) -> &Aabb {
+        self.sides.bounding_box()
+    }
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        self.sides.pdf_value(origin, direction)
+    }
+    fn random(&self, origin: &Point3) -> Vec3 {
+        self.sides.random(origin)
+    }
+    fn describe(&self) -> Option<ObjectDesc> {
+        // all sides share the material and normal map
+        let ShapeDesc::Quad {
+            normal_map,
+            material,
+            ..
+        } = self.sides.objects.first()?.describe()?.shape
+        else {
+            return None;
+        };
+        Some(
+            ShapeDesc::Cube {
+                a: self.a.e,
+                b: self.b.e,
+                normal_map,
+                material,
+            }
+            .into(),
+        )
+    }
+}
+
 pub fn cube<M: Material + Clone + 'static, N: NormalMap + Clone + 'static>(
     a: Point3,
     b: Point3,
     mat: M,
     nmap: N,
-) -> HittableList {
+) -> Cube {
     let mut sides = HittableList::default();
     let min = Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
     let max = Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
@@ -189,7 +246,7 @@ pub fn cube<M: Material + Clone + 'static, N: NormalMap + Clone + 'static>(
         nmap,
     )));
 
-    sides
+    Cube { sides, a, b }
 }
 
 // only for obj files
@@ -375,6 +432,7 @@ impl<M: Material> Hittable for Tria<M> {
             u,
             v,
             front_face: false,
+            color: Color::new(1.0, 1.0, 1.0),
         };
         rec_data.set_face_normal(r, normal);
         *rec = Some(rec_data);
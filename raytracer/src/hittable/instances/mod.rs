@@ -1,3 +1,4 @@
 pub mod constant_medium;
 pub mod flats;
 pub mod sphere;
+pub mod triangle_mesh;
@@ -1,7 +1,11 @@
 use crate::{
     hittable::{HitRecord, Hittable},
     materials::Material,
-    util::{aabb::Aabb, interval::Interval, onb::Onb, ray::Ray, vec3::*, INFINITY, PI},
+    util::{
+        aabb::Aabb, color::Color, interval::Interval, onb::Onb, random::rng, ray::Ray, vec3::*,
+        INFINITY, PI,
+    },
+    worlds::scene_file::desc::{MaterialRef, ObjectDesc, ShapeDesc},
 };
 use rand::Rng;
 
@@ -92,6 +96,7 @@ impl<M: Material> Hittable for Sphere<M> {
             u: 0.0,
             v: 0.0,
             front_face: false,
+            color: Color::new(1.0, 1.0, 1.0),
         };
         (rec_data.u, rec_data.v) = Self::get_sphere_uv(&outward_normal);
         rec_data.set_face_normal(r, outward_normal);
@@ -124,10 +129,25 @@ impl<M: Material> Hittable for Sphere<M> {
         uvw.build_from_w(&direction);
         uvw.local_vec(&random_to_sphere(self.radius, dist_squared))
     }
+    fn describe(&self) -> Option<ObjectDesc> {
+        Some(
+            ShapeDesc::Sphere {
+                center: self.center1.e,
+                radius: self.radius,
+                center2: if self.is_moving {
+                    Some((self.center1 + self.center_vec).e)
+                } else {
+                    None
+                },
+                material: Some(MaterialRef::Inline(Box::new(self.mat.describe()?))),
+            }
+            .into(),
+        )
+    }
 }
 
 pub fn random_to_sphere(radius: f64, distance_squared: f64) -> Vec3 {
-    let mut rng = rand::thread_rng();
+    let mut rng = rng();
     let r1 = rng.gen_range(0.0..1.0);
     let r2 = rng.gen_range(0.0..1.0);
     let z = 1.0 + r2 * ((1.0 - radius * radius / distance_squared).sqrt() - 1.0);
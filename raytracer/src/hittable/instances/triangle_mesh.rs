@@ -0,0 +1,318 @@
+// an indexed triangle mesh: every vertex is stored once and shared by the
+// faces around it, every submesh shares one material, and the faces are
+// found through a bvh over their indices, so a face costs a few u32s
+// instead of a whole copy of its vertices and material like Tria
+
+use crate::features::normal_map::NormalMap;
+use crate::hittable::bvh::{Bounded, BvhLayout, BvhStrategy, PrimitiveBvh};
+use crate::hittable::{HitRecord, Hittable};
+use crate::materials::Material;
+use crate::util::aabb::Aabb;
+use crate::util::color::Color;
+use crate::util::interval::Interval;
+use crate::util::onb::Onb;
+use crate::util::ray::Ray;
+use crate::util::vec3::*;
+use std::sync::Arc;
+
+// faces sharing a material
+pub struct Submesh {
+    pub indices: Vec<[u32; 3]>,
+    pub material: Arc<dyn Material>,
+    // needs uvs to orient it
+    pub normal_map: Option<Arc<dyn NormalMap>>,
+}
+
+#[derive(Clone, Copy)]
+struct Face {
+    v: [u32; 3],
+    submesh: u32,
+}
+
+// a face with its bounds, only while the bvh is built
+struct BoundedFace {
+    face: Face,
+    bbox: Aabb,
+}
+
+impl Bounded for BoundedFace {
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+pub struct TriangleMesh {
+    positions: Vec<Point3>,
+    // one per position, or empty for flat shading
+    normals: Vec<Vec3>,
+    // one per position, or empty to use the barycentric coordinates
+    uvs: Vec<(f64, f64)>,
+    // one per position, or empty for white
+    colors: Vec<Color>,
+    materials: Vec<Arc<dyn Material>>,
+    normal_maps: Vec<Option<Arc<dyn NormalMap>>>,
+    bvh: PrimitiveBvh<Face>,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<(f64, f64)>,
+        colors: Vec<Color>,
+        submeshes: Vec<Submesh>,
+        strategy: BvhStrategy,
+        layout: BvhLayout,
+    ) -> TriangleMesh {
+        assert!(normals.is_empty() || normals.len() == positions.len());
+        assert!(uvs.is_empty() || uvs.len() == positions.len());
+        assert!(colors.is_empty() || colors.len() == positions.len());
+
+        let mut faces = Vec::new();
+        let mut materials = Vec::with_capacity(submeshes.len());
+        let mut normal_maps = Vec::with_capacity(submeshes.len());
+        for (i, submesh) in submeshes.into_iter().enumerate() {
+            for v in submesh.indices {
+                let [a, b, c] = v.map(|i| positions[i as usize]);
+                let bbox = Aabb::new_aabb(&Aabb::new_diagonal(a, b), &Aabb::new_diagonal(a, c));
+                faces.push(BoundedFace {
+                    face: Face {
+                        v,
+                        submesh: i as u32,
+                    },
+                    bbox,
+                });
+            }
+            materials.push(submesh.material);
+            normal_maps.push(submesh.normal_map);
+        }
+
+        TriangleMesh {
+            positions,
+            normals,
+            uvs,
+            colors,
+            materials,
+            normal_maps,
+            bvh: PrimitiveBvh::build(faces, strategy, layout).map(|f| f.face),
+        }
+    }
+
+    // u and v directions of the face around `normal`, like Quad hands its
+    // edges to the normal map; None without uvs or for a degenerate mapping
+    fn tangent_frame(&self, face: &Face, normal: Vec3) -> Option<Onb> {
+        if self.uvs.is_empty() {
+            return None;
+        }
+        let [i0, i1, i2] = face.v.map(|i| i as usize);
+        let e1 = self.positions[i1] - self.positions[i0];
+        let e2 = self.positions[i2] - self.positions[i0];
+        let (du1, dv1) = (
+            self.uvs[i1].0 - self.uvs[i0].0,
+            self.uvs[i1].1 - self.uvs[i0].1,
+        );
+        let (du2, dv2) = (
+            self.uvs[i2].0 - self.uvs[i0].0,
+            self.uvs[i2].1 - self.uvs[i0].1,
+        );
+        let det = du1 * dv2 - du2 * dv1;
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let mut wtb = Onb::default();
+        wtb.axis[0] = unit_vector(&((e1 * dv2 - e2 * dv1) / det));
+        wtb.axis[1] = unit_vector(&((e2 * du1 - e1 * du2) / det));
+        wtb.axis[2] = normal;
+        Some(wtb)
+    }
+
+    // Moller-Trumbore, gives the distance and the barycentric coords of b and c
+    fn intersect(&self, face: &Face, r: &Ray, ray_t: &Interval) -> Option<(f64, f64, f64)> {
+        let [a, b, c] = face.v.map(|i| self.positions[i as usize]);
+        let e1 = b - a;
+        let e2 = c - a;
+        let p = cross(r.direction(), &e2);
+        let det = dot(&e1, &p);
+        if det.abs() < 1e-12 {
+            return None; // parallel to the face
+        }
+        let inv_det = 1.0 / det;
+        let s = r.origin() - &a;
+        let u = dot(&s, &p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = cross(&s, &e1);
+        let v = dot(r.direction(), &q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = dot(&e2, &q) * inv_det;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+        Some((t, u, v))
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
+        let mut closest = None;
+        let hit_anything = self.bvh.traverse(r, ray_t, |face, interval| {
+            let (t, u, v) = self.intersect(face, r, interval)?;
+            closest = Some((face, t, u, v));
+            Some(t)
+        });
+        let Some((face, t, b1, b2)) = closest else {
+            return false;
+        };
+
+        // only the closest face gets its normal and uv worked out
+        let [i0, i1, i2] = face.v.map(|i| i as usize);
+        let b0 = 1.0 - b1 - b2;
+        let [p0, p1, p2] = [self.positions[i0], self.positions[i1], self.positions[i2]];
+        let mut geometric = unit_vector(&cross(&(p1 - p0), &(p2 - p0)));
+        let shading = if self.normals.is_empty() {
+            geometric
+        } else {
+            unit_vector(&(self.normals[i0] * b0 + self.normals[i1] * b1 + self.normals[i2] * b2))
+        };
+        // vertex normals tell the outside, whatever the winding order
+        if dot(&geometric, &shading) < 0.0 {
+            geometric = -geometric;
+        }
+        let (u, v) = if self.uvs.is_empty() {
+            (b1, b2)
+        } else {
+            let (t0, t1, t2) = (self.uvs[i0], self.uvs[i1], self.uvs[i2]);
+            (
+                t0.0 * b0 + t1.0 * b1 + t2.0 * b2,
+                t0.1 * b0 + t1.1 * b1 + t2.1 * b2,
+            )
+        };
+
+        let color = if self.colors.is_empty() {
+            Color::new(1.0, 1.0, 1.0)
+        } else {
+            self.colors[i0] * b0 + self.colors[i1] * b1 + self.colors[i2] * b2
+        };
+
+        // which side was hit is up to the real face, a shading normal
+        // bent towards the ray must not change it
+        let front_face = dot(r.direction(), &geometric) < 0.0;
+        let mut normal = if front_face { shading } else { -shading };
+        if let Some(nmap) = &self.normal_maps[face.submesh as usize] {
+            if let Some(wtb) = self.tangent_frame(face, normal) {
+                // uvs repeat outside [0, 1), and the map wants them below 1
+                let wrap = |x: f64| x.rem_euclid(1.0).min(0.9999);
+                let (i, j) = nmap.convert((wrap(u), wrap(v)), (1.0, 1.0));
+                normal = unit_vector(&nmap.modify_normal((i, j), wtb));
+            }
+        }
+        *rec = Some(HitRecord {
+            p: r.at(t),
+            normal,
+            mat: &*self.materials[face.submesh as usize],
+            t,
+            u,
+            v,
+            front_face,
+            color,
+        });
+        hit_anything
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        self.bvh.bounds()
+    }
+}
+
+// vertex and index buffers as read from a file, before they are cleaned up
+// and handed to a TriangleMesh
+pub struct MeshBuffers {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub texs: Vec<(f64, f64)>,
+    // one per position, or empty
+    pub colors: Vec<Color>,
+    pub indices: Vec<[u32; 3]>,
+}
+
+impl MeshBuffers {
+    // faces no longer share corners, each corner takes its face's normal
+    pub fn flat_normals(&mut self) {
+        let mut positions = Vec::with_capacity(3 * self.indices.len());
+        let mut texs = Vec::new();
+        let mut colors = Vec::new();
+        for id in self.indices.iter_mut() {
+            let [p0, p1, p2] = id.map(|i| self.positions[i as usize]);
+            let n = unit_vector(&cross(&(p1 - p0), &(p2 - p0)));
+            for i in id.iter_mut() {
+                if !self.texs.is_empty() {
+                    texs.push(self.texs[*i as usize]);
+                }
+                if !self.colors.is_empty() {
+                    colors.push(self.colors[*i as usize]);
+                }
+                positions.push(self.positions[*i as usize]);
+                self.normals.push(n);
+                *i = positions.len() as u32 - 1;
+            }
+        }
+        self.positions = positions;
+        self.texs = texs;
+        self.colors = colors;
+    }
+
+    // every vertex averages the faces around it, weighted by their area
+    pub fn smooth_normals(&mut self) {
+        let mut sums = vec![Vec3::default(); self.positions.len()];
+        // a vertex whose faces cancel out keeps the normal of one of them
+        let mut fallback = vec![Vec3::new(0.0, 1.0, 0.0); self.positions.len()];
+        for id in self.indices.iter() {
+            let [p0, p1, p2] = id.map(|i| self.positions[i as usize]);
+            let n = cross(&(p1 - p0), &(p2 - p0));
+            for i in id {
+                sums[*i as usize] += n;
+                if n.length_squared() > 0.0 {
+                    fallback[*i as usize] = unit_vector(&n);
+                }
+            }
+        }
+        self.normals = sums
+            .iter()
+            .zip(fallback)
+            .map(|(n, fallback)| {
+                if n.length_squared() > 1e-24 {
+                    unit_vector(n)
+                } else {
+                    fallback
+                }
+            })
+            .collect();
+    }
+
+    // project onto the two longest sides of the model's box
+    pub fn planar_uvs(&mut self) {
+        let mut bbox = Aabb::EMPTY;
+        for p in self.positions.iter() {
+            bbox = Aabb::new_aabb(&bbox, &Aabb::new_diagonal(*p, *p));
+        }
+        let mut axes = [0, 1, 2];
+        axes.sort_by(|a, b| {
+            let size = |n| bbox.axis_interval(n).size();
+            size(*b).total_cmp(&size(*a))
+        });
+        let (a, b) = (bbox.axis_interval(axes[0]), bbox.axis_interval(axes[1]));
+        self.texs = self
+            .positions
+            .iter()
+            .map(|p| {
+                (
+                    (p.e[axes[0] as usize] - a.min) / a.size(),
+                    (p.e[axes[1] as usize] - b.min) / b.size(),
+                )
+            })
+            .collect();
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    hittable::{transforms::transform::Transform, HitRecord, Hittable},
+    materials::Material,
+    util::{aabb::Aabb, interval::Interval, mat4::Mat4, ray::Ray, vec3::*},
+    worlds::scene_file::desc::{MaterialRef, ObjectDesc},
+};
+use std::sync::Arc;
+
+// one placement of geometry that many instances share: a mesh or a bvh is
+// loaded once, wrapped in an Arc, and every copy only costs a matrix. an
+// instance may paint the shared geometry with its own material
+pub struct Instance {
+    object: Transform<Arc<dyn Hittable>>,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, m: Mat4) -> Self {
+        Instance {
+            object: Transform::new(object, m),
+            material: None,
+        }
+    }
+
+    // every hit uses this instead of what the geometry was built with
+    pub fn with_material(mut self, material: Arc<dyn Material>) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    // the same as on Transform, applied after what is there already
+    pub fn then(self, m: Mat4) -> Self {
+        Instance {
+            object: self.object.then(m),
+            material: self.material,
+        }
+    }
+    pub fn translate(self, offset: Vec3) -> Self {
+        self.then(Mat4::translation(offset))
+    }
+    pub fn rotate(self, axis: Vec3, angle: f64) -> Self {
+        self.then(Mat4::rotation(axis, angle))
+    }
+    pub fn scale(self, s: Vec3) -> Self {
+        self.then(Mat4::scaling(s))
+    }
+}
+
+impl Hittable for Instance {
+    fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
+        if !self.object.hit(r, ray_t, rec) {
+            return false;
+        }
+        if let (Some(material), Some(rec_data)) = (&self.material, rec) {
+            rec_data.mat = material.as_ref();
+        }
+        true
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        self.object.bounding_box()
+    }
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(origin, direction)
+    }
+    fn random(&self, origin: &Point3) -> Vec3 {
+        self.object.random(origin)
+    }
+    // the shared geometry is written out once per instance
+    fn describe(&self) -> Option<ObjectDesc> {
+        let mut desc = self.object.describe()?;
+        if let Some(material) = &self.material {
+            desc.material_override = Some(MaterialRef::Inline(Box::new(material.describe()?)));
+        }
+        Some(desc)
+    }
+}
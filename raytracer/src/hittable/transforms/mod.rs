@@ -1,2 +1,5 @@
+pub mod instance;
+pub mod motion;
 pub mod rotate_y;
+pub mod transform;
 pub mod translate;
@@ -0,0 +1,260 @@
+use crate::{
+    hittable::{
+        transforms::transform::{box_corners, hit_through, Transform},
+        HitRecord, Hittable,
+    },
+    util::{
+        aabb::Aabb, degrees_to_radians, interval::Interval, mat4::Mat4, ray::Ray, vec3::*, INFINITY,
+    },
+    worlds::scene_file::desc::{KeyframeDesc, ObjectDesc},
+};
+
+// sub steps per pair of keys when bounding the swept object
+const BBOX_STEPS: usize = 32;
+
+// a pose at some point in time: scaled, then rotated, then moved, the way
+// glTF nodes are
+#[derive(Clone, Copy)]
+pub struct Keyframe {
+    pub time: f64,
+    translate: Vec3,
+    rotate: [f64; 4], // unit quaternion x, y, z, w
+    scale: Vec3,
+}
+
+impl Keyframe {
+    pub fn new(time: f64) -> Self {
+        Keyframe {
+            time,
+            translate: Vec3::default(),
+            rotate: [0.0, 0.0, 0.0, 1.0],
+            scale: Vec3::new(1.0, 1.0, 1.0),
+        }
+    }
+    pub fn translate(mut self, offset: Vec3) -> Self {
+        self.translate = offset;
+        self
+    }
+    // degrees around an axis through the origin, after any earlier rotate.
+    // poses are blended the short way round, so a turn of 180 degrees or
+    // more between two keys needs keys in between
+    pub fn rotate(mut self, axis: Vec3, angle: f64) -> Self {
+        let a = unit_vector(&axis);
+        let (sin, cos) = (degrees_to_radians(angle) / 2.0).sin_cos();
+        let q = [a.x() * sin, a.y() * sin, a.z() * sin, cos];
+        self.rotate = quat_mul(q, self.rotate);
+        self
+    }
+    pub fn scale(mut self, s: Vec3) -> Self {
+        self.scale = s;
+        self
+    }
+    // replaces the rotation by a unit quaternion x, y, z, w, the way glTF
+    // and scene files store it
+    pub fn orientation(mut self, q: [f64; 4]) -> Self {
+        self.rotate = q;
+        self
+    }
+
+    fn matrix(&self) -> Mat4 {
+        Mat4::from_trs(self.translate.e, self.rotate, self.scale.e)
+    }
+
+    // f of the way from self to other
+    fn blend(&self, other: &Keyframe, f: f64) -> Keyframe {
+        Keyframe {
+            time: self.time + (other.time - self.time) * f,
+            translate: self.translate * (1.0 - f) + other.translate * f,
+            rotate: slerp(self.rotate, other.rotate, f),
+            scale: self.scale * (1.0 - f) + other.scale * f,
+        }
+    }
+}
+
+// Transform for an object that moves while the shutter is open: the pose
+// is blended between keyframes at the time of each ray, and holds still
+// before the first key and after the last
+pub struct Motion<H: Hittable> {
+    // the pose halfway through, which lights are sampled at; the first
+    // key's when a mirroring scale passes through zero there
+    rest: Transform<H>,
+    keys: Vec<Keyframe>,
+    bbox: Aabb,
+}
+
+impl<H: Hittable> Motion<H> {
+    // panics without keys or when the first key flattens the object away
+    pub fn new(object: H, mut keys: Vec<Keyframe>) -> Self {
+        assert!(!keys.is_empty(), "motion without keyframes");
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let first = keys[0];
+        let last = keys[keys.len() - 1];
+        let middle = pose(&keys, (first.time + last.time) / 2.0).matrix();
+        let rest = match middle.inverse() {
+            Some(_) => middle,
+            None => first.matrix(),
+        };
+        let bbox = sweep_box(object.bounding_box(), &keys);
+        Motion {
+            rest: Transform::new(object, rest),
+            keys,
+            bbox,
+        }
+    }
+}
+
+impl<H: Hittable> Hittable for Motion<H> {
+    fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
+        let m = pose(&self.keys, r.time()).matrix();
+        let Some(inv) = m.inverse() else {
+            return false;
+        };
+        hit_through(&self.rest.object, &m, &inv, r, ray_t, rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    // sampling and its pdf agree on the rest pose, so a moving light is
+    // still sampled without bias, just less well away from the middle
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        self.rest.pdf_value(origin, direction)
+    }
+    fn random(&self, origin: &Point3) -> Vec3 {
+        self.rest.random(origin)
+    }
+    fn describe(&self) -> Option<ObjectDesc> {
+        let mut desc = self.rest.object.describe()?;
+        // one set of keys per object
+        if !desc.motion.is_empty() {
+            return None;
+        }
+        desc.motion = self
+            .keys
+            .iter()
+            .map(|key| KeyframeDesc {
+                time: key.time,
+                translate: key.translate.e,
+                rotate: key.rotate,
+                scale: key.scale.e,
+            })
+            .collect();
+        Some(desc)
+    }
+}
+
+// the blended pose as a matrix, for keys sorted by time: worlds::animation
+// places objects with it frame by frame
+pub fn pose_matrix(keys: &[Keyframe], time: f64) -> Mat4 {
+    pose(keys, time).matrix()
+}
+
+fn pose(keys: &[Keyframe], time: f64) -> Keyframe {
+    let i = keys.partition_point(|k| k.time <= time);
+    if i == 0 {
+        keys[0]
+    } else if i == keys.len() {
+        keys[i - 1]
+    } else {
+        let (a, b) = (&keys[i - 1], &keys[i]);
+        a.blend(b, (time - a.time) / (b.time - a.time))
+    }
+}
+
+// the box over every pose: the corners are followed in small steps, and
+// as a corner strays from a straight step by at most the step's length,
+// the longest step pads the result
+fn sweep_box(bbox: &Aabb, keys: &[Keyframe]) -> Aabb {
+    let corners = box_corners(bbox);
+    let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
+    let mut max = Point3::new(-INFINITY, -INFINITY, -INFINITY);
+    let mut pad: f64 = 0.0;
+    let mut prev: Option<[Point3; 8]> = None;
+    let mut visit = |key: Keyframe| {
+        let m = key.matrix();
+        let points = corners.map(|c| m.point(&c));
+        for p in points.iter() {
+            for c in 0..3 {
+                min.e[c] = min.e[c].min(p.e[c]);
+                max.e[c] = max.e[c].max(p.e[c]);
+            }
+        }
+        if let Some(prev) = prev {
+            for (p, q) in points.iter().zip(prev.iter()) {
+                pad = pad.max((*p - *q).length());
+            }
+        }
+        prev = Some(points);
+    };
+    visit(keys[0]);
+    for pair in keys.windows(2) {
+        for step in 1..=BBOX_STEPS {
+            visit(pair[0].blend(&pair[1], step as f64 / BBOX_STEPS as f64));
+        }
+    }
+    let pad = Vec3::new(pad, pad, pad);
+    Aabb::new_diagonal(min - pad, max + pad)
+}
+
+// a after b
+fn quat_mul(a: [f64; 4], b: [f64; 4]) -> [f64; 4] {
+    let [ax, ay, az, aw] = a;
+    let [bx, by, bz, bw] = b;
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+fn slerp(a: [f64; 4], b: [f64; 4], f: f64) -> [f64; 4] {
+    let mut d: f64 = (0..4).map(|i| a[i] * b[i]).sum();
+    // q and -q are the same rotation, take the closer one
+    let b = if d < 0.0 {
+        d = -d;
+        b.map(|x| -x)
+    } else {
+        b
+    };
+    let (wa, wb) = if d > 0.9995 {
+        (1.0 - f, f)
+    } else {
+        let theta = d.acos();
+        let sin = theta.sin();
+        (((1.0 - f) * theta).sin() / sin, (f * theta).sin() / sin)
+    };
+    let q: [f64; 4] = std::array::from_fn(|i| a[i] * wa + b[i] * wb);
+    let len = q.iter().map(|x| x * x).sum::<f64>().sqrt();
+    q.map(|x| x / len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::instances::sphere::Sphere;
+    use crate::materials::lambertian::Lambertian;
+    use crate::textures::SolidColor;
+    use crate::util::color::Color;
+
+    #[test]
+    fn mirroring_keys_still_hit() {
+        // flipped through x, the pose halfway has no volume at all
+        let sphere = Sphere::new_static(
+            Point3::default(),
+            1.0,
+            Lambertian::<SolidColor>::new_color(Color::new(0.5, 0.5, 0.5)),
+        );
+        let keys = vec![
+            Keyframe::new(0.0),
+            Keyframe::new(1.0).scale(Vec3::new(-1.0, 1.0, 1.0)),
+        ];
+        let motion = Motion::new(sphere, keys);
+
+        let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let mut rec = None;
+        assert!(motion.hit(&r, &Interval::new(0.001, INFINITY), &mut rec));
+        assert!(motion.pdf_value(&Point3::new(0.0, 0.0, 5.0), &Vec3::new(0.0, 0.0, -1.0)) > 0.0);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     hittable::{HitRecord, Hittable},
     util::{aabb::Aabb, degrees_to_radians, interval::Interval, ray::Ray, vec3::*, INFINITY},
+    worlds::scene_file::desc::ObjectDesc,
 };
 
 pub struct RotateY<H: Hittable> {
@@ -86,4 +87,19 @@ impl<H: Hittable> Hittable for RotateY<H> {
     fn bounding_box(&self) -> &Aabb {
         &self.bbox
     }
+    fn describe(&self) -> Option<ObjectDesc> {
+        // rotate(translate(x, t)) == translate(rotate(x), rotated t), so the
+        // scene file order "rotate, then translate" still holds
+        let mut desc = self.object.describe()?;
+        let angle = self.sin_theta.atan2(self.cos_theta).to_degrees();
+        desc.rotate_y = Some(desc.rotate_y.unwrap_or(0.0) + angle);
+        desc.translate = desc.translate.map(|t| {
+            [
+                self.cos_theta * t[0] + self.sin_theta * t[2],
+                t[1],
+                -self.sin_theta * t[0] + self.cos_theta * t[2],
+            ]
+        });
+        Some(desc)
+    }
 }
@@ -0,0 +1,154 @@
+use crate::{
+    hittable::{HitRecord, Hittable},
+    util::{aabb::Aabb, interval::Interval, mat4::Mat4, ray::Ray, vec3::*, INFINITY},
+    worlds::scene_file::desc::ObjectDesc,
+};
+
+// any affine transform, where Translate and RotateY only do one thing each.
+// the object stays in its own space: rays are taken there by the inverse,
+// hits brought back by the matrix. chaining translate/rotate/scale folds
+// them into one matrix instead of nesting wrappers
+pub struct Transform<H: Hittable> {
+    pub(super) object: H,
+    m: Mat4,
+    inv: Mat4,
+    // how much the inverse scales volumes, for the pdf
+    inv_det: f64,
+    bbox: Aabb,
+}
+
+impl<H: Hittable> Transform<H> {
+    // panics on a singular matrix, which flattens the object away
+    pub fn new(object: H, m: Mat4) -> Self {
+        let inv = m.inverse().expect("transform is not invertible");
+        let bbox = transform_box(object.bounding_box(), &m);
+        Transform {
+            object,
+            m,
+            inv,
+            inv_det: inv.determinant().abs(),
+            bbox,
+        }
+    }
+
+    // applied after what is there already
+    pub fn then(self, m: Mat4) -> Self {
+        let m = m.mul(&self.m);
+        Self::new(self.object, m)
+    }
+    pub fn translate(self, offset: Vec3) -> Self {
+        self.then(Mat4::translation(offset))
+    }
+    // degrees around an axis through the origin
+    pub fn rotate(self, axis: Vec3, angle: f64) -> Self {
+        self.then(Mat4::rotation(axis, angle))
+    }
+    pub fn scale(self, s: Vec3) -> Self {
+        self.then(Mat4::scaling(s))
+    }
+}
+
+impl<H: Hittable> Hittable for Transform<H> {
+    fn hit<'a>(&'a self, r: &Ray, ray_t: &Interval, rec: &mut Option<HitRecord<'a>>) -> bool {
+        hit_through(&self.object, &self.m, &self.inv, r, ray_t, rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    // the object's pdf is over its own directions, a scale stretches them:
+    // dw_local / dw_world = |det inv| / |inv * w|^3 for a unit w
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let local = self.inv.vector(&unit_vector(direction));
+        let len = local.length();
+        self.object.pdf_value(&self.inv.point(origin), &local) * self.inv_det / (len * len * len)
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        // towards a local point from the local origin is towards the same
+        // point in world space
+        self.m.vector(&self.object.random(&self.inv.point(origin)))
+    }
+    fn describe(&self) -> Option<ObjectDesc> {
+        describe_through(self.object.describe()?, &self.m)
+    }
+}
+
+// `desc` seen through `m`: a turn around y and a move fold into rotate_y and
+// translate like RotateY and Translate do, anything else goes into the
+// matrix. None for a moving object, its keyframes come after any matrix
+pub fn describe_through(mut desc: ObjectDesc, m: &Mat4) -> Option<ObjectDesc> {
+    if !desc.motion.is_empty() {
+        return None;
+    }
+    match (desc.matrix, m.as_rotate_y()) {
+        (None, Some((angle, offset))) => {
+            if angle != 0.0 || desc.rotate_y.is_some() {
+                desc.rotate_y = Some(desc.rotate_y.unwrap_or(0.0) + angle);
+            }
+            if offset.e != [0.0; 3] || desc.translate.is_some() {
+                let t = desc.translate.map_or(Vec3::default(), |t| Vec3 { e: t });
+                desc.translate = Some(m.point(&t).e);
+            }
+        }
+        (inner, _) => {
+            let inner = inner.map_or(Mat4::IDENTITY, Mat4::from_cols);
+            desc.matrix = Some(m.mul(&inner).to_cols());
+        }
+    }
+    Some(desc)
+}
+
+// hits `object` seen through `m`, `inv` being its inverse
+pub fn hit_through<'a, H: Hittable>(
+    object: &'a H,
+    m: &Mat4,
+    inv: &Mat4,
+    r: &Ray,
+    ray_t: &Interval,
+    rec: &mut Option<HitRecord<'a>>,
+) -> bool {
+    // the direction is not normalized, so t means the same in both spaces
+    let local_r = Ray::new(inv.point(r.origin()), inv.vector(r.direction()), r.time());
+    if !object.hit(&local_r, ray_t, rec) {
+        return false;
+    }
+    let rec_data = if let Some(data) = rec {
+        data
+    } else {
+        panic!("No hit record!");
+    };
+    rec_data.p = m.point(&rec_data.p);
+    // the inverse transpose keeps dot(direction, normal) the same, so the
+    // normal still faces the ray and front_face holds
+    rec_data.normal = unit_vector(&inv.transpose_vector(&rec_data.normal));
+    true
+}
+
+pub fn transform_box(bbox: &Aabb, m: &Mat4) -> Aabb {
+    let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
+    let mut max = Point3::new(-INFINITY, -INFINITY, -INFINITY);
+    for corner in box_corners(bbox) {
+        let p = m.point(&corner);
+        for c in 0..3 {
+            min.e[c] = min.e[c].min(p.e[c]);
+            max.e[c] = max.e[c].max(p.e[c]);
+        }
+    }
+    Aabb::new_diagonal(min, max)
+}
+
+pub fn box_corners(bbox: &Aabb) -> [Point3; 8] {
+    std::array::from_fn(|corner| {
+        let pick = |axis: i32| {
+            let interval = bbox.axis_interval(axis);
+            if corner >> axis & 1 == 1 {
+                interval.max
+            } else {
+                interval.min
+            }
+        };
+        Point3::new(pick(0), pick(1), pick(2))
+    })
+}
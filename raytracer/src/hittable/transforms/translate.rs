@@ -1,6 +1,7 @@
 use crate::{
     hittable::{HitRecord, Hittable},
     util::{aabb::Aabb, interval::Interval, ray::Ray, vec3::Vec3},
+    worlds::scene_file::desc::ObjectDesc,
 };
 
 pub struct Translate<H: Hittable> {
@@ -41,4 +42,11 @@ impl<H: Hittable> Hittable for Translate<H> {
     fn bounding_box(&self) -> &Aabb {
         &self.bbox
     }
+    fn describe(&self) -> Option<ObjectDesc> {
+        // translations add up
+        let mut desc = self.object.describe()?;
+        let offset = desc.translate.map_or(Vec3::default(), |t| Vec3 { e: t }) + self.offset;
+        desc.translate = Some(offset.e);
+        Some(desc)
+    }
 }
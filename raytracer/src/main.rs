@@ -1,5 +1,6 @@
 #[allow(dead_code)]
 mod camera;
+mod cli;
 #[allow(dead_code)]
 mod features;
 #[allow(dead_code)]
@@ -8,6 +9,7 @@ mod hittable;
 mod materials;
 #[allow(dead_code)]
 mod pdf;
+mod render;
 #[allow(dead_code)]
 mod textures;
 #[allow(dead_code)]
@@ -15,204 +17,239 @@ mod util;
 #[allow(dead_code)]
 mod worlds;
 
-use crate::camera::background::BackGround;
-use crate::camera::Camera;
+use crate::cli::{print_scenes, CliArgs, Frames, USAGE};
 use crate::features::edge_detect::combination;
 use crate::features::edge_detect::edge_detecting;
-use crate::hittable::hittable_list::HittableList;
-use crate::hittable::Hittable;
-use crate::pdf::hittable_pdf::HittablePdf;
-use crate::pdf::Pdf;
-use crate::util::color::{put_color, Color};
-use crate::util::interval::Interval;
-use crate::util::ray::Ray;
-use crate::util::{
-    OutputParam, IMAGE_HEIGHT, IMAGE_WIDTH, INFINITY, MAX_RECURSE_DEPTH, THREAD_NUM,
-};
-use crate::worlds::final_scene::final_scene;
-use image::{ImageBuffer, Rgb, RgbImage};
-use indicatif::ProgressBar;
-use rand::Rng;
+use crate::features::gltf::load_gltf;
+use crate::hittable::bvh::take_build_stats;
+use crate::render::{render, Accum, PassParam};
+use crate::util::image_output::{save_float, to_ldr, OutputFormat};
+use crate::util::{random, OutputParam, RenderParam};
+use crate::worlds::scene_file::export::export_scene;
+use crate::worlds::scene_file::{load_scene, SceneError};
+use crate::worlds::{animation_by_name, scene_by_name, Scene};
+use image::Rgb32FImage;
 use std::fs;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::process;
 use std::time::Instant;
-use threadpool::ThreadPool;
-
-fn ray_color(
-    r: &Ray,
-    depth: i32,
-    world: &HittableList,
-    lights: &HittableList,
-    background: Color,
-    back_ground_illum: Color,
-) -> Color {
-    if depth <= 0 {
-        return Color::default();
+
+fn process_and_output(img: &Rgb32FImage, param: &OutputParam) {
+    // do edge, gauss and gray here
+    let dir = Path::new(param.savedir);
+    let savefile = dir.join(param.savefile);
+
+    if !dir.exists() {
+        fs::create_dir_all(dir).expect("Couldn't create directory");
     }
-    let mut rec = None;
-    if !world.hit(r, &Interval::new(0.001, INFINITY), &mut rec) {
-        if depth == MAX_RECURSE_DEPTH {
-            return background;
-        } else {
-            return back_ground_illum;
+
+    let format = OutputFormat::from_path(&savefile);
+    if format.is_float() {
+        if param.enable_edge_detect {
+            eprintln!("note: edge detection only applies to 8-bit output, skipped");
         }
-    }
-    let rec = if let Some(data) = rec {
-        data
-    } else {
-        panic!("No hit record!");
-    };
-    let emission_color = rec.mat.emitted(r, &rec, rec.u, rec.v, &rec.p);
-    let mut srec = None;
-    if !rec.mat.scatter(r, &rec, &mut srec) {
-        return emission_color;
-    }
-    let srec = if let Some(data) = srec {
-        data
-    } else {
-        panic!("No scatter record!");
-    };
-    if srec.skip_pdf {
-        // do not need to consider scatter
-        return srec.attenuation
-            * ray_color(
-                &srec.skip_pdf_ray,
-                depth - 1,
-                world,
-                lights,
-                background,
-                back_ground_illum,
-            );
+        save_float(img, &savefile, format).expect("Failed to save!");
+        return;
     }
 
-    let light_pdf = HittablePdf::new(lights, rec.p);
+    let img = to_ldr(img, &param.tone);
 
-    let p = (&light_pdf, &*srec.pdf_ptr.expect("No pdf defined!"));
-    let mut rng = rand::thread_rng();
-    let p_generate = if rng.gen_range(0.0..1.0) < 0.5 {
-        p.0.generate()
-    } else {
-        p.1.generate()
-    };
-    let scattered = Ray::new(rec.p, p_generate, r.time());
-    let pdf_val = 0.5 * p.0.value(scattered.direction()) + 0.5 * p.1.value(scattered.direction());
-    // 直接将mixturePdf拆了，要不然dyn无法去掉
-
-    let scatter_pdf = rec.mat.scattering_pdf(r, &rec, &scattered);
-
-    let sample_color = ray_color(
-        &scattered,
-        depth - 1,
-        world,
-        lights,
-        background,
-        back_ground_illum,
-    );
-    let scatter_color = (sample_color * srec.attenuation * scatter_pdf) / pdf_val;
+    if param.enable_edge_detect {
+        let edged_img = edge_detecting(&img);
+        let img = combination(&img, edged_img);
+        img.save(savefile).expect("Failed to save!");
+        return;
+    }
 
-    emission_color + scatter_color
+    img.save(savefile).expect("Failed to save!");
 }
 
-fn render(
-    world: HittableList,
-    lights: HittableList,
-    cam: Camera,
-    back_ground: BackGround,
-) -> RgbImage {
-    let progress_bar = if option_env!("CI").unwrap_or_default() == "true" {
-        ProgressBar::hidden()
-    } else {
-        ProgressBar::new((IMAGE_HEIGHT * IMAGE_WIDTH) as u64)
-    };
-    let progress_bar = Arc::new(Mutex::new(progress_bar));
-
-    let img = RgbImage::new(IMAGE_WIDTH, IMAGE_HEIGHT);
-    let img = Arc::new(Mutex::new(img));
-
-    let world = Arc::new(world);
-    let lights = Arc::new(lights);
-    let cam = Arc::new(cam);
-    let back_ground = Arc::new(back_ground);
-
-    let pool = ThreadPool::new(THREAD_NUM);
-
-    for j in 0..IMAGE_HEIGHT {
-        let img = Arc::clone(&img);
-        let progress_bar = Arc::clone(&progress_bar);
-        let world = Arc::clone(&world);
-        let lights = Arc::clone(&lights);
-        let cam = Arc::clone(&cam);
-        let back_ground = Arc::clone(&back_ground);
-        pool.execute(move || {
-            for i in 0..IMAGE_WIDTH {
-                let mut pixel_color = Color::default();
-                let background_color = back_ground.value(i, j);
-                let background_illum = Color::new(0.6, 0.8, 1.0) * 0.5;
-
-                for s_j in 0..cam.sqrt_spp {
-                    for s_i in 0..cam.sqrt_spp {
-                        let r = cam.get_ray(i, j, s_i, s_j);
-                        pixel_color += ray_color(
-                            &r,
-                            MAX_RECURSE_DEPTH,
-                            &world,
-                            &lights,
-                            background_color,
-                            background_illum,
-                        );
-                    }
-                }
-                pixel_color *= cam.pixel_samples_scale;
-
-                let mut img = img.lock().unwrap();
-                let pixel = img.get_pixel_mut(i, j);
-                let (r, g, b) = put_color(&pixel_color);
-                *pixel = Rgb([r as u8, g as u8, b as u8]);
-
-                let progress_bar = progress_bar.lock().unwrap();
-                progress_bar.inc(1);
+fn build_scene(args: &CliArgs) -> Scene {
+    if let Some(path) = &args.scene_file {
+        let lower = path.to_lowercase();
+        let loaded = if lower.ends_with(".gltf") || lower.ends_with(".glb") {
+            load_gltf(path, &args.render).map_err(|err| err.to_string())
+        } else {
+            load_scene(path, &args.render).map_err(|err| err.to_string())
+        };
+        return match loaded {
+            Ok(scene) => scene,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                process::exit(1);
             }
-        });
+        };
+    }
+    match scene_by_name(&args.scene) {
+        Some(builder) => builder(&args.render),
+        None => {
+            eprintln!("error: unknown scene '{}'", args.scene);
+            print_scenes();
+            process::exit(2);
+        }
     }
-    pool.join();
-    progress_bar.lock().unwrap().finish();
-    Arc::try_unwrap(img).unwrap().into_inner().unwrap()
 }
 
-fn process_and_output(img: ImageBuffer<Rgb<u8>, Vec<u8>>, param: OutputParam) {
-    // do edge, gauss and gray here
-    let savedir = String::from(param.savedir);
-    let savefile = savedir.clone() + &*String::from(param.savefile);
+// renders one image and saves it, previews along the way
+fn render_image(
+    scene: &Scene,
+    render_param: &RenderParam,
+    passes: &PassParam,
+    accum: Accum,
+    output_param: &OutputParam,
+) -> Accum {
+    let accum = render(
+        scene,
+        render_param,
+        passes,
+        accum,
+        // previews overwrite the output, so stopping early keeps the latest one
+        |img, passes| {
+            process_and_output(img, output_param);
+            eprintln!("\npreview after {} passes written", passes);
+        },
+    );
+    process_and_output(&accum.image(), output_param);
+    accum
+}
 
-    let dir = Path::new(&savedir);
-    if !dir.exists() {
-        fs::create_dir_all(dir).expect("Couldn't create directory");
+fn render_frames(args: &CliArgs, frames: Frames) {
+    if args.scene_file.is_some() || args.export.is_some() {
+        eprintln!("error: --frames renders animated scenes picked with -s");
+        process::exit(2);
     }
+    let Some(builder) = animation_by_name(&args.scene) else {
+        eprintln!("error: '{}' is not an animated scene", args.scene);
+        print_scenes();
+        process::exit(2);
+    };
+    let animation = builder();
+    let (first, last) = match frames {
+        Frames::All => animation.frames,
+        Frames::Range(first, last) => (first, last),
+    };
+    let output = Path::new(&args.output);
+    let savedir = output.parent().and_then(|d| d.to_str()).unwrap_or("");
+    let ext = output.extension().and_then(|e| e.to_str()).unwrap_or("png");
 
-    if param.enable_edge_detect {
-        let edged_img = edge_detecting(&img);
-        let img = combination(&img, edged_img);
-        img.save(savefile).expect("Failed to save!");
-        return;
-    }
+    let mut scene = None;
+    for frame in first..=last {
+        if !animation.camera_only {
+            scene = None;
+        }
+        let scene = scene.get_or_insert_with(|| {
+            // the same seed for every frame, so whatever the builder
+            // scatters at random stays where it was
+            random::seed(args.render.seed);
+            (animation.build)(&args.render, frame as f64)
+        });
+        if args.bvh_stats {
+            for stats in take_build_stats() {
+                eprintln!("bvh {}", stats);
+            }
+        }
+        let cam = &mut scene.2;
+        animation.camera.apply(cam, frame as f64);
 
-    img.save(savefile).expect("Failed to save!");
+        let savefile = format!("frame_{:04}.{}", frame, ext);
+        let output_param = OutputParam {
+            enable_edge_detect: args.edge_detect || cam.edge_detect,
+            tone: args.tone,
+            savefile: &savefile,
+            savedir,
+        };
+        // but a new noise pattern, which flickers less than a fixed one
+        let mut render_param = args.render.clone();
+        render_param.seed = args.render.seed.wrapping_add(frame as u64);
+        let accum = Accum::new(cam.image_width, cam.image_height());
+
+        eprintln!("frame {} of {}..={}", frame, first, last);
+        render_image(scene, &render_param, &args.passes, accum, &output_param);
+    }
 }
 
 fn main() {
+    let args = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("error: {}", msg);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", USAGE);
+        return;
+    }
+    if args.list_scenes {
+        print_scenes();
+        return;
+    }
+
     let now = Instant::now();
 
-    let (world, lights, cam, back_ground) = final_scene();
-    let raw_img = render(world, lights, cam, back_ground);
+    if let Some(frames) = args.frames {
+        render_frames(&args, frames);
+        eprintln!();
+        eprintln!("duration:{:?}ms", now.elapsed().as_millis());
+        return;
+    }
 
+    random::seed(args.render.seed);
+    let scene = build_scene(&args);
+    if args.bvh_stats {
+        for stats in take_build_stats() {
+            eprintln!("bvh {}", stats);
+        }
+    }
+    let (world, lights, cam, back_ground) = &scene;
+    if let Some(path) = &args.export {
+        let exported = export_scene(world, lights, cam, back_ground).and_then(|src| {
+            fs::write(path, src).map_err(|err| SceneError {
+                file: Some(path.clone()),
+                line: None,
+                msg: format!("cannot write scene file: {}", err),
+            })
+        });
+        if let Err(err) = exported {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+        eprintln!("scene written to {}", path);
+        return;
+    }
+    let output = Path::new(&args.output);
     let output_param = OutputParam {
-        enable_edge_detect: false,
-        savefile: "/202.png",
-        savedir: "output/book0",
+        enable_edge_detect: args.edge_detect || cam.edge_detect,
+        tone: args.tone,
+        savefile: output
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or("out.png"),
+        savedir: output.parent().and_then(|d| d.to_str()).unwrap_or(""),
+    };
+
+    let (image_width, image_height) = (cam.image_width, cam.image_height());
+    let accum = match &args.passes.checkpoint {
+        Some(checkpoint) if checkpoint.resume => {
+            match Accum::load(&checkpoint.path, image_width, image_height, cam.sqrt_spp) {
+                Ok(accum) => {
+                    eprintln!("resuming after {} passes", accum.passes());
+                    accum
+                }
+                Err(err) => {
+                    eprintln!("error: {}: {}", checkpoint.path, err);
+                    process::exit(1);
+                }
+            }
+        }
+        _ => Accum::new(image_width, image_height),
     };
 
-    process_and_output(raw_img, output_param);
+    let accum = render_image(&scene, &args.render, &args.passes, accum, &output_param);
+    if let Some(path) = &args.sample_map {
+        accum.sample_map().save(path).expect("Failed to save!");
+    }
 
     let now = now.elapsed().as_millis();
     eprintln!();
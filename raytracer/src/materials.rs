@@ -3,12 +3,16 @@ pub mod diffuse_light;
 pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
+pub mod microfacet;
+pub mod principled;
 
 use crate::{
     hittable::HitRecord,
     pdf::Pdf,
     util::{color::Color, ray::Ray, vec3::*},
+    worlds::scene_file::desc::MaterialDesc,
 };
+use std::sync::Arc;
 
 #[derive(Default)]
 pub struct ScatterRecord {
@@ -44,4 +48,45 @@ pub trait Material: Send + Sync {
     fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
         0.0
     }
+    // what light coming back along `scattered` is multiplied by, the brdf
+    // times the cosine. that is the attenuation times scattering_pdf for
+    // most materials, those whose color depends on the direction override it
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        srec.attenuation * self.scattering_pdf(r_in, rec, scattered)
+    }
+    // how to write this material into a scene file, None if it cannot be
+    fn describe(&self) -> Option<MaterialDesc> {
+        None
+    }
+}
+
+// shared materials, e.g. the ones a scene file refers to by name
+impl<M: Material + ?Sized> Material for Arc<M> {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut Option<ScatterRecord>) -> bool {
+        (**self).scatter(r_in, rec, srec)
+    }
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
+        (**self).emitted(r_in, rec, u, v, p)
+    }
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        (**self).scattering_pdf(r_in, rec, scattered)
+    }
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        (**self).scattering_color(r_in, rec, srec, scattered)
+    }
+    fn describe(&self) -> Option<MaterialDesc> {
+        (**self).describe()
+    }
 }
@@ -1,17 +1,84 @@
 use crate::{
     hittable::HitRecord,
     materials::{Material, ScatterRecord},
-    util::{color::Color, ray::Ray, vec3::*},
+    pdf::{dielectric_pdf::DielectricPdf, Pdf},
+    util::{
+        color::Color,
+        ggx,
+        onb::Onb,
+        random::rng,
+        ray::Ray,
+        spectrum::{Dispersion, D_LINE},
+        vec3::*,
+    },
+    worlds::scene_file::desc::MaterialDesc,
 };
 use rand::Rng;
 
 pub struct Dielectric {
     refraction_index: f64,
+    // 0 is polished, above that the surface is frosted: rays are reflected
+    // and refracted off GGX microfacets (Walter et al. 2007)
+    roughness: f64,
+    // a sheet with no thickness, e.g. a window pane: what is not reflected
+    // passes straight through
+    thin: bool,
+    // Beer-Lambert coefficients, how fast light dies out inside, and the
+    // color and distance they were given as
+    absorption: Color,
+    tint: Option<(Color, f64)>,
+    // how the index changes with the wavelength of spectral rays, scaled
+    // so it is refraction_index at the d line
+    dispersion: Option<Dispersion>,
 }
 
 impl Dielectric {
     pub fn new(refraction_index: f64) -> Dielectric {
-        Dielectric { refraction_index }
+        Dielectric {
+            refraction_index,
+            roughness: 0.0,
+            thin: false,
+            absorption: Color::default(),
+            tint: None,
+            dispersion: None,
+        }
+    }
+    pub fn with_roughness(mut self, roughness: f64) -> Self {
+        self.roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
+    pub fn thin(mut self) -> Self {
+        self.thin = true;
+        self
+    }
+    // light that travels `distance` through the glass comes out multiplied
+    // by `color`
+    pub fn with_tint(mut self, color: Color, distance: f64) -> Self {
+        let absorb = |c: f64| -c.clamp(1e-6, 1.0).ln() / distance;
+        self.absorption = Color::new(absorb(color.x()), absorb(color.y()), absorb(color.z()));
+        self.tint = Some((color, distance));
+        self
+    }
+
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Self {
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    // the index for this ray's wavelength. one below 1, a pocket of air
+    // in glass, is the glass's turned around
+    fn index(&self, r_in: &Ray) -> f64 {
+        match self.dispersion {
+            Some(d) if r_in.wavelength() > 0.0 => {
+                let ratio = d.ior(r_in.wavelength()) / d.ior(D_LINE);
+                if self.refraction_index >= 1.0 {
+                    self.refraction_index * ratio
+                } else {
+                    self.refraction_index / ratio
+                }
+            }
+            _ => self.refraction_index,
+        }
     }
 
     fn reflectance(&self, cosine: f64, refraction_index: f64) -> f64 {
@@ -20,35 +87,121 @@ impl Dielectric {
         r0 = r0 * r0;
         r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
     }
+
+    // what is left of light that crossed the inside to reach this hit
+    fn transmittance(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        if rec.front_face || self.thin {
+            return Color::new(1.0, 1.0, 1.0);
+        }
+        let distance = rec.t * r_in.direction().length();
+        let a = self.absorption * -distance;
+        Color::new(a.x().exp(), a.y().exp(), a.z().exp())
+    }
+
+    // index below the surface over the index above, the ray coming from above
+    fn eta(&self, r_in: &Ray, rec: &HitRecord) -> f64 {
+        if rec.front_face {
+            self.index(r_in)
+        } else {
+            1.0 / self.index(r_in)
+        }
+    }
+
+    fn is_rough(&self) -> bool {
+        self.roughness > 0.0 && !self.thin
+    }
 }
 
 impl Material for Dielectric {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut Option<ScatterRecord>) -> bool {
-        let mut rng = rand::thread_rng();
-        let ri = if rec.front_face {
-            1.0 / self.refraction_index
-        } else {
-            self.refraction_index
-        };
+        let attenuation = self.transmittance(r_in, rec);
+        if self.is_rough() {
+            let wo = -unit_vector(r_in.direction());
+            if dot(&wo, &rec.normal) <= 0.0 {
+                // seen from below its shading normal
+                return false;
+            }
+            let alpha = ggx::alpha(self.roughness);
+            *srec = Some(ScatterRecord::new(
+                attenuation,
+                Some(Box::new(DielectricPdf::new(
+                    rec.normal,
+                    &wo,
+                    alpha,
+                    self.eta(r_in, rec),
+                ))),
+                false,
+                Ray::default(),
+            ));
+            return true;
+        }
+
+        let mut rng = rng();
+        let ri = 1.0 / self.eta(r_in, rec);
         let unit_direction = unit_vector(r_in.direction());
         let cos_theta = dot(&-unit_direction, &rec.normal).min(1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
-        let cannot_refract = ri * sin_theta > 1.0;
-
-        let direction =
+        let direction = if self.thin {
+            // the light bouncing back and forth inside the sheet adds to
+            // what one surface reflects
+            let r = self.reflectance(cos_theta, self.index(r_in));
+            let t = 1.0 - r;
+            let r = r + t * t * r / (1.0 - r * r);
+            if r > rng.gen_range(0.0..1.0) {
+                reflect(&unit_direction, &rec.normal)
+            } else {
+                unit_direction
+            }
+        } else {
+            let cannot_refract = ri * sin_theta > 1.0;
             if cannot_refract || self.reflectance(cos_theta, ri) > rng.gen_range(0.0..1.0) {
                 reflect(&unit_direction, &rec.normal)
             } else {
                 refract(&unit_direction, &rec.normal, ri)
-            };
+            }
+        };
 
         *srec = Some(ScatterRecord::new(
-            Color::new(1.0, 1.0, 1.0),
+            attenuation,
             None,
             true,
             Ray::new(rec.p, direction, r_in.time()),
         ));
         true
     }
+
+    // only rough glass goes through here, smooth glass skips the pdf
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let alpha = ggx::alpha(self.roughness);
+        DielectricPdf::new(rec.normal, &-r_in.direction(), alpha, self.eta(r_in, rec))
+            .value(scattered.direction())
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        let mut uvw = Onb::default();
+        uvw.build_from_w(&rec.normal);
+        let wo = uvw.local_coords(&-unit_vector(r_in.direction()));
+        let wi = uvw.local_coords(&unit_vector(scattered.direction()));
+        let alpha = ggx::alpha(self.roughness);
+        let (value, _) = ggx::dielectric(&wo, &wi, alpha, self.eta(r_in, rec));
+        srec.attenuation * value
+    }
+
+    fn describe(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Dielectric {
+            refraction_index: self.refraction_index,
+            roughness: (self.roughness > 0.0).then_some(self.roughness),
+            thin: self.thin,
+            tint: self.tint.map(|(color, _)| color.e),
+            tint_distance: self.tint.map(|(_, distance)| distance),
+            dispersion: self.dispersion,
+        })
+    }
 }
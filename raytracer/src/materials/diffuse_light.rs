@@ -1,32 +1,94 @@
+pub mod profile;
+
 use crate::{
     hittable::HitRecord,
-    materials::Material,
+    materials::{diffuse_light::profile::EmissionProfile, Material},
     textures::{SolidColor, Texture},
-    util::{color::Color, ray::Ray, vec3::*},
+    util::{color::Color, ray::Ray, spectrum::blackbody, vec3::*},
+    worlds::scene_file::desc::{MaterialDesc, ProfileDesc},
 };
 
+// an emitter: the texture's color, times the intensity, tinted by the
+// color of a black body if it has a temperature, and shaped by a profile.
+// only the front face glows unless it is two sided
 #[derive(Clone)]
 pub struct DiffuseLight<T: Texture> {
     tex: T,
+    intensity: f64,
+    two_sided: bool,
+    temperature: Option<f64>,
+    tint: Color, // of the temperature, white without one
+    profile: EmissionProfile,
 }
 
 impl<T: Texture> DiffuseLight<T> {
-    pub fn _new_tex(tex: T) -> Self {
-        DiffuseLight { tex }
-    }
-    pub fn new_color(emit: Color) -> DiffuseLight<SolidColor> {
+    pub fn new_tex(tex: T) -> Self {
         DiffuseLight {
-            tex: SolidColor::new_color(emit),
+            tex,
+            intensity: 1.0,
+            two_sided: false,
+            temperature: None,
+            tint: Color::new(1.0, 1.0, 1.0),
+            profile: EmissionProfile::Uniform,
         }
     }
+    pub fn new_color(emit: Color) -> DiffuseLight<SolidColor> {
+        DiffuseLight::new_tex(SolidColor::new_color(emit))
+    }
+    pub fn two_sided(mut self) -> Self {
+        self.two_sided = true;
+        self
+    }
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+    // in kelvin, e.g. 2700 for a warm bulb or 6500 for daylight
+    pub fn with_temperature(mut self, kelvin: f64) -> Self {
+        self.temperature = Some(kelvin);
+        self.tint = blackbody(kelvin);
+        self
+    }
+    pub fn with_profile(mut self, profile: EmissionProfile) -> Self {
+        self.profile = profile;
+        self
+    }
 }
 
 impl<T: Texture> Material for DiffuseLight<T> {
-    fn emitted(&self, _r_in: &Ray, rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
-        if rec.front_face {
-            self.tex.value(u, v, p)
-        } else {
-            Color::default()
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
+        if !rec.front_face && !self.two_sided {
+            return Color::default();
         }
+        let factor = match self.profile {
+            EmissionProfile::Uniform => 1.0,
+            // the normal faces the ray, on either side
+            _ => self
+                .profile
+                .factor(dot(&-unit_vector(r_in.direction()), &rec.normal)),
+        };
+        if factor <= 0.0 {
+            return Color::default();
+        }
+        self.tex.value(u, v, p) * self.tint * (self.intensity * factor)
+    }
+    fn describe(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::DiffuseLight {
+            emit: self.tex.describe()?.into(),
+            intensity: (self.intensity != 1.0).then_some(self.intensity),
+            two_sided: self.two_sided,
+            temperature: self.temperature,
+            profile: match &self.profile {
+                EmissionProfile::Uniform => None,
+                EmissionProfile::Spot { exponent, cone } => Some(ProfileDesc::Spot {
+                    exponent: *exponent,
+                    cone: *cone,
+                }),
+                EmissionProfile::Table(table) => Some(ProfileDesc::Table {
+                    angles: table.angles().to_vec(),
+                    values: table.values().to_vec(),
+                }),
+            },
+        })
     }
 }
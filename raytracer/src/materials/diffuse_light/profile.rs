@@ -0,0 +1,191 @@
+// how the light an emitter gives off changes with the angle to its normal.
+// profiles only scale the emitted radiance, so lights are still sampled by
+// their shape and stay unbiased, just noisier where the profile is narrow
+
+use crate::util::PI;
+
+#[derive(Clone, Debug)]
+pub enum EmissionProfile {
+    // the same in every direction, a lambertian emitter
+    Uniform,
+    // cos^exponent of the angle to the normal, and nothing past `cone`
+    // degrees from it
+    Spot { exponent: f64, cone: f64 },
+    // measured, e.g. from an IES file
+    Table(AngularTable),
+}
+
+impl EmissionProfile {
+    // what the radiance leaving at `cos` to the normal is scaled by
+    pub fn factor(&self, cos: f64) -> f64 {
+        match self {
+            EmissionProfile::Uniform => 1.0,
+            EmissionProfile::Spot { exponent, cone } => {
+                if cos <= 0.0 || cos < (cone * PI / 180.0).cos() {
+                    0.0
+                } else {
+                    cos.powf(*exponent)
+                }
+            }
+            EmissionProfile::Table(table) => table.at(cos.clamp(-1.0, 1.0).acos() * 180.0 / PI),
+        }
+    }
+}
+
+// values at angles in degrees from the normal, linear in between and 0
+// past the last angle. the largest value is scaled to 1, the light's
+// intensity sets how bright it is
+#[derive(Clone, Debug)]
+pub struct AngularTable {
+    angles: Vec<f64>,
+    values: Vec<f64>,
+}
+
+impl AngularTable {
+    pub fn new(angles: Vec<f64>, values: Vec<f64>) -> Result<AngularTable, String> {
+        if angles.is_empty() || angles.len() != values.len() {
+            return Err(String::from("a profile needs as many values as angles"));
+        }
+        if angles.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(String::from("profile angles must be increasing"));
+        }
+        if values.iter().any(|v| *v < 0.0) {
+            return Err(String::from("profile values cannot be negative"));
+        }
+        let max = values.iter().cloned().fold(0.0, f64::max);
+        if max <= 0.0 {
+            return Err(String::from("a profile that is 0 everywhere"));
+        }
+        let values = values.iter().map(|v| v / max).collect();
+        Ok(AngularTable { angles, values })
+    }
+
+    // an IES LM-63 photometric file of type C, with vertical angle 0 (straight
+    // down from the fixture) along the normal. the candelas are averaged
+    // over the horizontal angles, so the light is the same all around
+    pub fn from_ies(text: &str) -> Result<AngularTable, String> {
+        let mut lines = text.lines();
+        let tilt = lines
+            .by_ref()
+            .map(str::trim)
+            .find(|l| l.starts_with("TILT="))
+            .ok_or_else(|| String::from("no TILT= line, not an IES file"))?;
+        if tilt != "TILT=NONE" {
+            return Err(format!("'{}' is not supported, only TILT=NONE", tilt));
+        }
+        let rest: Vec<f64> = lines
+            .flat_map(|l| l.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|t| !t.is_empty())
+            .map(|t| t.parse::<f64>().map_err(|_| format!("bad number '{}'", t)))
+            .collect::<Result<_, _>>()?;
+        // lamps, lumens, multiplier, vertical and horizontal angle counts,
+        // photometric type, units, width, length, height, ballast factor,
+        // future use and watts
+        if rest.len() < 13 {
+            return Err(String::from("the IES header is cut short"));
+        }
+        let (n_v, n_h) = (rest[3] as usize, rest[4] as usize);
+        if rest[5] != 1.0 {
+            return Err(String::from("only type C photometry is supported"));
+        }
+        let data = &rest[13..];
+        if n_v == 0 || n_h == 0 || data.len() < n_v + n_h + n_v * n_h {
+            return Err(String::from("fewer candela values than angles"));
+        }
+        let angles = data[..n_v].to_vec();
+        let candela = &data[n_v + n_h..n_v + n_h + n_v * n_h];
+        let values = (0..n_v)
+            .map(|i| (0..n_h).map(|h| candela[h * n_v + i]).sum::<f64>() / n_h as f64)
+            .collect();
+        AngularTable::new(angles, values)
+    }
+
+    pub fn angles(&self) -> &[f64] {
+        &self.angles
+    }
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    fn at(&self, angle: f64) -> f64 {
+        let i = self.angles.partition_point(|a| *a <= angle);
+        if i == 0 {
+            self.values[0]
+        } else if i == self.angles.len() {
+            if angle == self.angles[i - 1] {
+                self.values[i - 1]
+            } else {
+                0.0
+            }
+        } else {
+            let (a0, a1) = (self.angles[i - 1], self.angles[i]);
+            let f = (angle - a0) / (a1 - a0);
+            self.values[i - 1] * (1.0 - f) + self.values[i] * f
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // three vertical angles by two horizontal ones, the candelas of each
+    // horizontal angle on a line of their own
+    const IES: &str = "IESNA:LM-63-2002
+[MANUFAC] made by hand
+TILT=NONE
+1 1000 1 3 2 1 1 0.5 0.5 0.1
+1.0 1.0 60
+0 45 90
+0, 90
+100 80 0
+100 60 0
+";
+
+    #[test]
+    fn reads_an_ies_file() {
+        let table = AngularTable::from_ies(IES).unwrap();
+        assert_eq!(table.angles(), [0.0, 45.0, 90.0]);
+        // averaged around and scaled to 1 at the top
+        assert_eq!(table.values(), [1.0, 0.7, 0.0]);
+
+        let profile = EmissionProfile::Table(table);
+        let cos = |angle: f64| (angle * PI / 180.0).cos();
+        assert!((profile.factor(cos(22.5)) - 0.85).abs() < 1e-9);
+        assert_eq!(profile.factor(cos(120.0)), 0.0);
+    }
+
+    #[test]
+    fn rejects_broken_ies_files() {
+        let err = |text: &str| AngularTable::from_ies(text).err().unwrap();
+
+        assert_eq!(
+            err("IESNA:LM-63-2002\n1 1000 1"),
+            "no TILT= line, not an IES file"
+        );
+        assert_eq!(
+            err(&IES.replace("TILT=NONE", "TILT=INCLUDE")),
+            "'TILT=INCLUDE' is not supported, only TILT=NONE"
+        );
+        assert_eq!(
+            err(&IES[..IES.find("1.0 1.0 60").unwrap()]),
+            "the IES header is cut short"
+        );
+        assert_eq!(
+            err(&IES.replace("1 3 2 1 1", "1 3 2 2 1")),
+            "only type C photometry is supported"
+        );
+        assert_eq!(
+            err(IES.trim_end().trim_end_matches("100 60 0")),
+            "fewer candela values than angles"
+        );
+        assert_eq!(
+            err(&IES.replace("0 45 90", "0 45 ninety")),
+            "bad number 'ninety'"
+        );
+        assert_eq!(
+            err(&IES.replace("0 45 90", "0 90 45")),
+            "profile angles must be increasing"
+        );
+    }
+}
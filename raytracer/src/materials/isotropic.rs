@@ -3,7 +3,8 @@ use crate::{
     materials::{Material, ScatterRecord},
     pdf::sphere_pdf::SpherePdf,
     textures::{SolidColor, Texture},
-    util::{color::Color, ray::Ray, PI},
+    util::{color::Color, ray::Ray, vec3::Point3, PI},
+    worlds::scene_file::desc::MaterialDesc,
 };
 
 pub struct Isotropic {
@@ -34,4 +35,9 @@ impl Material for Isotropic {
     fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
         1.0 / (4.0 * PI)
     }
+    fn describe(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Isotropic {
+            albedo: self.tex.value(0.0, 0.0, &Point3::default()).e,
+        })
+    }
 }
@@ -4,6 +4,7 @@ use crate::{
     pdf::cosine_pdf::CosinePdf,
     textures::{SolidColor, Texture},
     util::{color::Color, ray::Ray, vec3::*, PI},
+    worlds::scene_file::desc::MaterialDesc,
 };
 
 #[derive(Clone)]
@@ -25,7 +26,7 @@ impl<T: Texture> Lambertian<T> {
 impl<T: Texture> Material for Lambertian<T> {
     fn scatter(&self, _r_in: &Ray, rec: &HitRecord, srec: &mut Option<ScatterRecord>) -> bool {
         *srec = Some(ScatterRecord::new(
-            self.tex.value(rec.u, rec.v, &rec.p),
+            self.tex.value(rec.u, rec.v, &rec.p) * rec.color,
             Some(Box::new(CosinePdf::new(rec.normal))),
             false,
             Ray::default(),
@@ -36,4 +37,9 @@ impl<T: Texture> Material for Lambertian<T> {
         let cos_theta = dot(&rec.normal, &unit_vector(scattered.direction()));
         (cos_theta / PI).max(0.0)
     }
+    fn describe(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Lambertian {
+            albedo: self.tex.describe()?.into(),
+        })
+    }
 }
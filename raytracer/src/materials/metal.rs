@@ -3,6 +3,7 @@ use crate::{
     materials::{Material, ScatterRecord},
     textures::{SolidColor, Texture},
     util::{color::Color, ray::Ray, vec3::*},
+    worlds::scene_file::desc::MaterialDesc,
 };
 
 #[derive(Clone)]
@@ -33,11 +34,17 @@ impl<T: Texture> Material for Metal<T> {
         reflected = unit_vector(&reflected) + (random_unit_vector() * self.fuzz);
 
         *srec = Some(ScatterRecord::new(
-            self.tex.value(rec.u, rec.v, &rec.p),
+            self.tex.value(rec.u, rec.v, &rec.p) * rec.color,
             None,
             true,
             Ray::new(rec.p, reflected, r_in.time()),
         ));
         true
     }
+    fn describe(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Metal {
+            albedo: self.tex.describe()?.into(),
+            fuzz: self.fuzz,
+        })
+    }
 }
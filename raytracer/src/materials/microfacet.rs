@@ -0,0 +1,129 @@
+use crate::{
+    hittable::HitRecord,
+    materials::{Material, ScatterRecord},
+    pdf::{ggx_pdf::GgxPdf, Pdf},
+    textures::{SolidColor, Texture},
+    util::{color::Color, ggx, onb::Onb, ray::Ray, vec3::*, PI},
+    worlds::scene_file::desc::{MaterialDesc, ScalarRef},
+};
+
+// reflectance of dielectrics at normal incidence, the usual 4%
+const DIELECTRIC_F0: f64 = 0.04;
+
+// the metallic-roughness model of glTF and most engines: a GGX specular
+// lobe with Smith masking and Schlick Fresnel over a Lambertian base.
+// metals tint their reflection by the base color and have no diffuse part.
+// metallic and roughness are read from the first channel of their textures
+#[derive(Clone)]
+pub struct Microfacet<T: Texture, M: Texture = SolidColor, R: Texture = SolidColor> {
+    base_color: T,
+    metallic: M,
+    roughness: R,
+}
+
+impl<T: Texture, M: Texture, R: Texture> Microfacet<T, M, R> {
+    pub fn new_color(base_color: Color, metallic: f64, roughness: f64) -> Microfacet<SolidColor> {
+        Microfacet {
+            base_color: SolidColor::new_color(base_color),
+            metallic: SolidColor::new_color(Color::new(metallic, metallic, metallic)),
+            roughness: SolidColor::new_color(Color::new(roughness, roughness, roughness)),
+        }
+    }
+    pub fn new_tex(base_color: T, metallic: M, roughness: R) -> Self {
+        Microfacet {
+            base_color,
+            metallic,
+            roughness,
+        }
+    }
+
+    // (base color, metallic, alpha) at the hit
+    fn at(&self, rec: &HitRecord) -> (Color, f64, f64) {
+        let metallic = self.metallic.value(rec.u, rec.v, &rec.p).x();
+        let roughness = self.roughness.value(rec.u, rec.v, &rec.p).x();
+        (
+            self.base_color.value(rec.u, rec.v, &rec.p) * rec.color,
+            metallic.clamp(0.0, 1.0),
+            ggx::alpha(roughness.clamp(0.0, 1.0)),
+        )
+    }
+}
+
+// how often the specular lobe is sampled: always for metals, half the
+// time for dielectrics
+fn specular_weight(metallic: f64) -> f64 {
+    0.5 + 0.5 * metallic
+}
+
+impl<T: Texture, M: Texture, R: Texture> Material for Microfacet<T, M, R> {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut Option<ScatterRecord>) -> bool {
+        let (base_color, metallic, alpha) = self.at(rec);
+        let wo = -unit_vector(r_in.direction());
+        if dot(&wo, &rec.normal) <= 0.0 {
+            // seen from below its shading normal
+            return false;
+        }
+        *srec = Some(ScatterRecord::new(
+            base_color,
+            Some(Box::new(GgxPdf::new(
+                rec.normal,
+                &wo,
+                alpha,
+                specular_weight(metallic),
+            ))),
+            false,
+            Ray::default(),
+        ));
+        true
+    }
+
+    // the density scatter's pdf gives the direction
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let (_, metallic, alpha) = self.at(rec);
+        let wo = -r_in.direction();
+        GgxPdf::new(rec.normal, &wo, alpha, specular_weight(metallic)).value(scattered.direction())
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        let (_, metallic, alpha) = self.at(rec);
+        let base_color = srec.attenuation;
+        let mut uvw = Onb::default();
+        uvw.build_from_w(&rec.normal);
+        let wo = uvw.local_coords(&-unit_vector(r_in.direction()));
+        let wi = uvw.local_coords(&unit_vector(scattered.direction()));
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::default();
+        }
+        let h = unit_vector(&(wo + wi));
+        let cos_h = dot(&wi, &h);
+
+        // per channel Fresnel, the dielectric 4% blended into the base color
+        let fresnel = |c: f64| {
+            let f0 = DIELECTRIC_F0 * (1.0 - metallic) + c * metallic;
+            ggx::schlick(f0, cos_h)
+        };
+        let f = Color::new(
+            fresnel(base_color.x()),
+            fresnel(base_color.y()),
+            fresnel(base_color.z()),
+        );
+        let specular = f * (ggx::d(&h, alpha) * ggx::g2(&wo, &wi, alpha) / (4.0 * wo.z() * wi.z()));
+        // what the specular reflection does not take goes into the base
+        let diffuse = (Color::new(1.0, 1.0, 1.0) - f) * base_color * ((1.0 - metallic) / PI);
+        (specular + diffuse) * wi.z()
+    }
+
+    fn describe(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Microfacet {
+            base_color: self.base_color.describe()?.into(),
+            metallic: ScalarRef::from_texture(self.metallic.describe()?),
+            roughness: ScalarRef::from_texture(self.roughness.describe()?),
+        })
+    }
+}
@@ -0,0 +1,299 @@
+use crate::{
+    hittable::HitRecord,
+    materials::{Material, ScatterRecord},
+    pdf::{
+        principled_pdf::{LobeWeights, PrincipledPdf},
+        Pdf,
+    },
+    textures::{SolidColor, Texture},
+    util::{color::Color, ggx, onb::Onb, ray::Ray, vec3::*, PI},
+    worlds::scene_file::desc::{MaterialDesc, ScalarRef},
+};
+use std::sync::Arc;
+
+// roughness of the clearcoat's masking, fixed as in Burley's model
+const CLEARCOAT_MASKING_ALPHA: f64 = 0.25;
+
+// the inputs of a principled surface, every one a texture. scalars are read
+// from the first channel and kept in [0, 1]
+#[derive(Clone)]
+pub struct PrincipledParam {
+    pub base_color: Arc<dyn Texture>,
+    pub metallic: Arc<dyn Texture>,
+    pub roughness: Arc<dyn Texture>,
+    // dielectric reflectance, 0.5 is the usual 4%
+    pub specular: Arc<dyn Texture>,
+    // how much that reflectance takes on the hue of the base color
+    pub specular_tint: Arc<dyn Texture>,
+    // a grazing retro-reflection for cloth
+    pub sheen: Arc<dyn Texture>,
+    pub sheen_tint: Arc<dyn Texture>,
+    // a second, colorless specular layer on top
+    pub clearcoat: Arc<dyn Texture>,
+    pub clearcoat_gloss: Arc<dyn Texture>,
+    // how much of the dielectric part is glass instead of diffuse
+    pub transmission: Arc<dyn Texture>,
+    // flattens the diffuse towards Hanrahan-Krueger single scattering
+    pub subsurface: Arc<dyn Texture>,
+    // index of refraction of the transmissive part
+    pub ior: f64,
+}
+
+fn gray(x: f64) -> Arc<dyn Texture> {
+    Arc::new(SolidColor::new_color(Color::new(x, x, x)))
+}
+
+impl Default for PrincipledParam {
+    fn default() -> Self {
+        PrincipledParam {
+            base_color: gray(0.8),
+            metallic: gray(0.0),
+            roughness: gray(0.5),
+            specular: gray(0.5),
+            specular_tint: gray(0.0),
+            sheen: gray(0.0),
+            sheen_tint: gray(0.5),
+            clearcoat: gray(0.0),
+            clearcoat_gloss: gray(1.0),
+            transmission: gray(0.0),
+            subsurface: gray(0.0),
+            ior: 1.5,
+        }
+    }
+}
+
+// Burley's principled BSDF (Disney 2012, with the transmission of 2015): a
+// retro-reflective diffuse with a subsurface look and sheen, a GGX specular
+// lobe, a GTR1 clearcoat and a rough glass lobe. metallic fades out the
+// diffuse and glass, transmission trades the diffuse for glass
+#[derive(Clone)]
+pub struct Principled {
+    param: PrincipledParam,
+}
+
+// the inputs read at one hit
+struct Surface {
+    base_color: Color,
+    metallic: f64,
+    roughness: f64,
+    specular: f64,
+    specular_tint: f64,
+    sheen: f64,
+    sheen_tint: f64,
+    clearcoat: f64,
+    clearcoat_gloss: f64,
+    transmission: f64,
+    subsurface: f64,
+}
+
+impl Surface {
+    fn alpha(&self) -> f64 {
+        ggx::alpha(self.roughness)
+    }
+    fn clearcoat_alpha(&self) -> f64 {
+        0.1 + (0.001 - 0.1) * self.clearcoat_gloss
+    }
+    fn diffuse_weight(&self) -> f64 {
+        (1.0 - self.metallic) * (1.0 - self.transmission)
+    }
+    fn glass_weight(&self) -> f64 {
+        (1.0 - self.metallic) * self.transmission
+    }
+
+    // the base color with its luminance taken out
+    fn tint(&self) -> Color {
+        let c = self.base_color;
+        let lum = 0.3 * c.x() + 0.6 * c.y() + 0.1 * c.z();
+        if lum > 0.0 {
+            c / lum
+        } else {
+            Color::new(1.0, 1.0, 1.0)
+        }
+    }
+
+    // how often scatter picks each lobe, roughly what each reflects
+    fn lobe_weights(&self) -> LobeWeights {
+        let diffuse = self.diffuse_weight();
+        let specular = self.metallic + diffuse;
+        let clearcoat = 0.25 * self.clearcoat;
+        let transmission = self.glass_weight();
+        let sum = diffuse + specular + clearcoat + transmission;
+        LobeWeights {
+            diffuse: diffuse / sum,
+            specular: specular / sum,
+            clearcoat: clearcoat / sum,
+            transmission: transmission / sum,
+        }
+    }
+}
+
+fn lerp(a: Color, b: Color, t: f64) -> Color {
+    a * (1.0 - t) + b * t
+}
+
+fn schlick_weight(cos: f64) -> f64 {
+    (1.0 - cos).clamp(0.0, 1.0).powi(5)
+}
+
+impl Principled {
+    pub fn new(param: PrincipledParam) -> Self {
+        Principled { param }
+    }
+
+    fn at(&self, rec: &HitRecord) -> Surface {
+        let scalar = |tex: &Arc<dyn Texture>| tex.value(rec.u, rec.v, &rec.p).x().clamp(0.0, 1.0);
+        let p = &self.param;
+        Surface {
+            base_color: p.base_color.value(rec.u, rec.v, &rec.p) * rec.color,
+            metallic: scalar(&p.metallic),
+            roughness: scalar(&p.roughness),
+            specular: scalar(&p.specular),
+            specular_tint: scalar(&p.specular_tint),
+            sheen: scalar(&p.sheen),
+            sheen_tint: scalar(&p.sheen_tint),
+            clearcoat: scalar(&p.clearcoat),
+            clearcoat_gloss: scalar(&p.clearcoat_gloss),
+            transmission: scalar(&p.transmission),
+            subsurface: scalar(&p.subsurface),
+        }
+    }
+
+    // index below the surface over the index above, the ray coming from above
+    fn eta(&self, rec: &HitRecord) -> f64 {
+        if rec.front_face {
+            self.param.ior
+        } else {
+            1.0 / self.param.ior
+        }
+    }
+
+    fn pdf(&self, s: &Surface, rec: &HitRecord, wo: &Vec3) -> PrincipledPdf {
+        PrincipledPdf::new(
+            rec.normal,
+            wo,
+            s.lobe_weights(),
+            s.alpha(),
+            s.clearcoat_alpha(),
+            self.eta(rec),
+        )
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut Option<ScatterRecord>) -> bool {
+        let s = self.at(rec);
+        let wo = -unit_vector(r_in.direction());
+        if dot(&wo, &rec.normal) <= 0.0 {
+            // seen from below its shading normal
+            return false;
+        }
+        *srec = Some(ScatterRecord::new(
+            s.base_color,
+            Some(Box::new(self.pdf(&s, rec, &wo))),
+            false,
+            Ray::default(),
+        ));
+        true
+    }
+
+    // the density scatter's pdf gives the direction
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let s = self.at(rec);
+        self.pdf(&s, rec, &-r_in.direction())
+            .value(scattered.direction())
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        _srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        let s = self.at(rec);
+        let mut uvw = Onb::default();
+        uvw.build_from_w(&rec.normal);
+        let wo = uvw.local_coords(&-unit_vector(r_in.direction()));
+        let wi = uvw.local_coords(&unit_vector(scattered.direction()));
+        if wo.z() <= 0.0 {
+            return Color::default();
+        }
+        let white = Color::new(1.0, 1.0, 1.0);
+        let mut color = Color::default();
+
+        if wi.z() > 0.0 {
+            let h = unit_vector(&(wo + wi));
+            let cos_d = dot(&wi, &h);
+            let tint = s.tint();
+            let (fl, fv) = (schlick_weight(wi.z()), schlick_weight(wo.z()));
+
+            // diffuse, darker or brighter at grazing angles with roughness,
+            // blended with the flatter single scattering approximation
+            let fd90 = 0.5 + 2.0 * cos_d * cos_d * s.roughness;
+            let fd = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+            let fss90 = cos_d * cos_d * s.roughness;
+            let fss = (1.0 + (fss90 - 1.0) * fl) * (1.0 + (fss90 - 1.0) * fv);
+            let ss = 1.25 * (fss * (1.0 / (wi.z() + wo.z()) - 0.5) + 0.5);
+            let diffuse = s.base_color * ((fd + (ss - fd) * s.subsurface) / PI);
+            let sheen = lerp(white, tint, s.sheen_tint) * (s.sheen * schlick_weight(cos_d));
+            color += (diffuse + sheen) * s.diffuse_weight();
+
+            // specular, tinted by the base color as the surface gets metallic
+            let alpha = s.alpha();
+            let f0 = lerp(
+                lerp(white, tint, s.specular_tint) * (0.08 * s.specular),
+                s.base_color,
+                s.metallic,
+            );
+            let fresnel = |c: f64| ggx::schlick(c, cos_d);
+            let f = Color::new(fresnel(f0.x()), fresnel(f0.y()), fresnel(f0.z()));
+            let dg = ggx::d(&h, alpha) * ggx::g2(&wo, &wi, alpha) / (4.0 * wo.z() * wi.z());
+            color += f * (dg * (1.0 - s.glass_weight()));
+
+            // clearcoat, a fixed 4% on a GTR1 lobe
+            if s.clearcoat > 0.0 {
+                let g =
+                    ggx::g1(&wo, CLEARCOAT_MASKING_ALPHA) * ggx::g1(&wi, CLEARCOAT_MASKING_ALPHA);
+                let d = ggx::gtr1(&h, s.clearcoat_alpha());
+                let f = ggx::schlick(0.04, cos_d);
+                color += white * (0.25 * s.clearcoat * f * d * g / (4.0 * wo.z() * wi.z()));
+            }
+            color *= wi.z();
+        }
+
+        // glass, light passing through is tinted by the square root of the
+        // base color each time it crosses, so going in and out again gives
+        // the base color once, as in Disney 2015 and pbrt
+        if s.glass_weight() > 0.0 {
+            let (value, _) = ggx::dielectric(&wo, &wi, s.alpha(), self.eta(rec));
+            let tint = if wi.z() < 0.0 {
+                Color {
+                    e: s.base_color.e.map(|c| c.max(0.0).sqrt()),
+                }
+            } else {
+                white
+            };
+            color += tint * (value * s.glass_weight());
+        }
+        color
+    }
+
+    fn describe(&self) -> Option<MaterialDesc> {
+        let p = &self.param;
+        let scalar = |tex: &Arc<dyn Texture>| Some(ScalarRef::from_texture(tex.describe()?));
+        Some(MaterialDesc::Principled {
+            base_color: p.base_color.describe()?.into(),
+            metallic: scalar(&p.metallic)?,
+            roughness: scalar(&p.roughness)?,
+            specular: scalar(&p.specular)?,
+            specular_tint: scalar(&p.specular_tint)?,
+            sheen: scalar(&p.sheen)?,
+            sheen_tint: scalar(&p.sheen_tint)?,
+            clearcoat: scalar(&p.clearcoat)?,
+            clearcoat_gloss: scalar(&p.clearcoat_gloss)?,
+            transmission: scalar(&p.transmission)?,
+            subsurface: scalar(&p.subsurface)?,
+            ior: p.ior,
+        })
+    }
+}
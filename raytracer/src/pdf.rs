@@ -1,6 +1,9 @@
 pub mod cosine_pdf;
+pub mod dielectric_pdf;
+pub mod ggx_pdf;
 pub mod hittable_pdf;
 pub mod mixture_pdf;
+pub mod principled_pdf;
 pub mod sphere_pdf;
 
 use crate::util::vec3::*;
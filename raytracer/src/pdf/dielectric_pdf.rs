@@ -0,0 +1,39 @@
+use crate::{
+    pdf::Pdf,
+    util::{ggx, onb::Onb, vec3::*},
+};
+
+// what rough glass samples: reflection off or refraction through a visible
+// GGX normal, picked by its Fresnel reflectance
+pub struct DielectricPdf {
+    uvw: Onb,
+    wo: Vec3, // towards the viewer, in uvw
+    alpha: f64,
+    eta: f64, // index below the surface over the index above
+}
+
+impl DielectricPdf {
+    pub fn new(normal: Vec3, wo: &Vec3, alpha: f64, eta: f64) -> DielectricPdf {
+        let mut uvw = Onb::default();
+        uvw.build_from_w(&normal);
+        let wo = uvw.local_coords(&unit_vector(wo));
+        DielectricPdf {
+            uvw,
+            wo,
+            alpha,
+            eta,
+        }
+    }
+}
+
+impl Pdf for DielectricPdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        let wi = self.uvw.local_coords(&unit_vector(direction));
+        ggx::dielectric(&self.wo, &wi, self.alpha, self.eta).1
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.uvw
+            .local_vec(&ggx::sample_dielectric(&self.wo, self.alpha, self.eta))
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{
+    pdf::Pdf,
+    util::{ggx, onb::Onb, random::rng, vec3::*, PI},
+};
+use rand::Rng;
+
+// what a microfacet surface samples: a reflection off a visible GGX normal
+// with probability `specular`, else a cosine weighted diffuse bounce
+pub struct GgxPdf {
+    uvw: Onb,
+    wo: Vec3, // towards the viewer, in uvw
+    alpha: f64,
+    specular: f64,
+}
+
+impl GgxPdf {
+    pub fn new(normal: Vec3, wo: &Vec3, alpha: f64, specular: f64) -> GgxPdf {
+        let mut uvw = Onb::default();
+        uvw.build_from_w(&normal);
+        let wo = uvw.local_coords(&unit_vector(wo));
+        GgxPdf {
+            uvw,
+            wo,
+            alpha,
+            specular,
+        }
+    }
+}
+
+impl Pdf for GgxPdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        let wi = self.uvw.local_coords(&unit_vector(direction));
+        let diffuse = (wi.z() / PI).max(0.0);
+        let specular = ggx::reflection_pdf(&self.wo, &wi, self.alpha);
+        self.specular * specular + (1.0 - self.specular) * diffuse
+    }
+
+    fn generate(&self) -> Vec3 {
+        if rng().gen_range(0.0..1.0) < self.specular {
+            let h = ggx::sample_visible_normal(&self.wo, self.alpha);
+            self.uvw.local_vec(&reflect(&-self.wo, &h))
+        } else {
+            self.uvw.local_vec(&random_cosine_direction())
+        }
+    }
+}
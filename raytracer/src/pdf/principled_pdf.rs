@@ -0,0 +1,87 @@
+use crate::{
+    pdf::Pdf,
+    util::{ggx, onb::Onb, random::rng, vec3::*, PI},
+};
+use rand::Rng;
+
+// how often each lobe of a principled surface is sampled, summing to 1
+#[derive(Clone, Copy, Default)]
+pub struct LobeWeights {
+    pub diffuse: f64,
+    pub specular: f64,
+    pub clearcoat: f64,
+    pub transmission: f64,
+}
+
+// what a principled surface samples: one lobe is picked by its weight and
+// drawn from, the density is that of the whole mix so any direction, e.g.
+// one towards a light, can be weighed
+pub struct PrincipledPdf {
+    uvw: Onb,
+    wo: Vec3, // towards the viewer, in uvw
+    weights: LobeWeights,
+    alpha: f64,
+    clearcoat_alpha: f64,
+    eta: f64, // index below the surface over the index above
+}
+
+impl PrincipledPdf {
+    pub fn new(
+        normal: Vec3,
+        wo: &Vec3,
+        weights: LobeWeights,
+        alpha: f64,
+        clearcoat_alpha: f64,
+        eta: f64,
+    ) -> PrincipledPdf {
+        let mut uvw = Onb::default();
+        uvw.build_from_w(&normal);
+        let wo = uvw.local_coords(&unit_vector(wo));
+        PrincipledPdf {
+            uvw,
+            wo,
+            weights,
+            alpha,
+            clearcoat_alpha,
+            eta,
+        }
+    }
+}
+
+impl Pdf for PrincipledPdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        let wi = self.uvw.local_coords(&unit_vector(direction));
+        let w = &self.weights;
+        let mut pdf = 0.0;
+        if w.diffuse > 0.0 {
+            pdf += w.diffuse * (wi.z() / PI).max(0.0);
+        }
+        if w.specular > 0.0 {
+            pdf += w.specular * ggx::reflection_pdf(&self.wo, &wi, self.alpha);
+        }
+        if w.clearcoat > 0.0 {
+            pdf += w.clearcoat * ggx::gtr1_reflection_pdf(&self.wo, &wi, self.clearcoat_alpha);
+        }
+        if w.transmission > 0.0 {
+            pdf += w.transmission * ggx::dielectric(&self.wo, &wi, self.alpha, self.eta).1;
+        }
+        pdf
+    }
+
+    fn generate(&self) -> Vec3 {
+        let w = &self.weights;
+        let pick = rng().gen_range(0.0..1.0);
+        let wi = if pick < w.diffuse {
+            random_cosine_direction()
+        } else if pick < w.diffuse + w.specular {
+            let h = ggx::sample_visible_normal(&self.wo, self.alpha);
+            reflect(&-self.wo, &h)
+        } else if pick < w.diffuse + w.specular + w.clearcoat {
+            let h = ggx::sample_gtr1(self.clearcoat_alpha);
+            reflect(&-self.wo, &h)
+        } else {
+            ggx::sample_dielectric(&self.wo, self.alpha, self.eta)
+        };
+        self.uvw.local_vec(&wi)
+    }
+}
@@ -0,0 +1,434 @@
+// the render loop: samples are taken in passes over the whole image, tile by
+// tile, and accumulated per pixel, so a usable image exists long before the
+// last pass
+
+pub mod adaptive;
+pub mod checkpoint;
+pub mod tiles;
+
+use crate::hittable::hittable_list::HittableList;
+use crate::hittable::Hittable;
+use crate::pdf::hittable_pdf::HittablePdf;
+use crate::pdf::Pdf;
+use crate::render::adaptive::AdaptiveParam;
+use crate::render::checkpoint::CheckpointParam;
+use crate::render::tiles::{tiles, Tile, TileOrder, DEFAULT_TILE_SIZE};
+use crate::util::color::Color;
+use crate::util::image_output::linear_pixel;
+use crate::util::interval::Interval;
+use crate::util::random::{self, rng};
+use crate::util::ray::Ray;
+use crate::util::spectrum;
+use crate::util::{RenderParam, INFINITY};
+use crate::worlds::Scene;
+use image::{GrayImage, Luma, Rgb32FImage};
+use indicatif::ProgressBar;
+use rand::Rng;
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use rayon::ThreadPoolBuilder;
+use std::time::{Duration, Instant};
+
+fn ray_color(
+    r: &Ray,
+    depth: i32,
+    max_depth: i32,
+    world: &HittableList,
+    lights: &HittableList,
+    background: Color,
+    back_ground_illum: Color,
+) -> Color {
+    if depth <= 0 {
+        return Color::default();
+    }
+    let mut rec = None;
+    if !world.hit(r, &Interval::new(0.001, INFINITY), &mut rec) {
+        if depth == max_depth {
+            return background;
+        } else {
+            return back_ground_illum;
+        }
+    }
+    let rec = if let Some(data) = rec {
+        data
+    } else {
+        panic!("No hit record!");
+    };
+    let emission_color = rec.mat.emitted(r, &rec, rec.u, rec.v, &rec.p);
+    let mut srec = None;
+    if !rec.mat.scatter(r, &rec, &mut srec) {
+        return emission_color;
+    }
+    let mut srec = if let Some(data) = srec {
+        data
+    } else {
+        panic!("No scatter record!");
+    };
+    if srec.skip_pdf {
+        // do not need to consider scatter
+        return srec.attenuation
+            * ray_color(
+                &srec.skip_pdf_ray.with_wavelength(r.wavelength()),
+                depth - 1,
+                max_depth,
+                world,
+                lights,
+                background,
+                back_ground_illum,
+            );
+    }
+
+    let light_pdf = HittablePdf::new(lights, rec.p);
+
+    let material_pdf = srec.pdf_ptr.take().expect("No pdf defined!");
+    let p = (&light_pdf, &*material_pdf);
+    let mut rng = rng();
+    let p_generate = if rng.gen_range(0.0..1.0) < 0.5 {
+        p.0.generate()
+    } else {
+        p.1.generate()
+    };
+    let scattered = Ray::new(rec.p, p_generate, r.time()).with_wavelength(r.wavelength());
+    let pdf_val = 0.5 * p.0.value(scattered.direction()) + 0.5 * p.1.value(scattered.direction());
+    // 直接将mixturePdf拆了，要不然dyn无法去掉
+
+    let scatter_color = rec.mat.scattering_color(r, &rec, &srec, &scattered);
+
+    let sample_color = ray_color(
+        &scattered,
+        depth - 1,
+        max_depth,
+        world,
+        lights,
+        background,
+        back_ground_illum,
+    );
+    let scatter_color = (sample_color * scatter_color) / pdf_val;
+
+    emission_color + scatter_color
+}
+
+// write the image so far every `every_passes` passes and/or `every_secs`
+// seconds; with neither set all samples are taken in one go
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PreviewParam {
+    pub every_passes: Option<u32>,
+    pub every_secs: Option<f64>,
+}
+
+impl PreviewParam {
+    pub fn is_enabled(&self) -> bool {
+        self.every_passes.is_some() || self.every_secs.is_some()
+    }
+
+    fn is_due(&self, passes: u32, elapsed: Duration) -> bool {
+        self.every_passes.map_or(false, |n| passes >= n)
+            || self
+                .every_secs
+                .map_or(false, |secs| elapsed.as_secs_f64() >= secs)
+    }
+}
+
+// running sums of one pixel; the luminance mean/variance (Welford) drive
+// adaptive sampling
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PixelStats {
+    pub sum: Color,
+    pub count: u32,
+    pub mean: f64,
+    pub m2: f64,
+}
+
+impl PixelStats {
+    fn add(&mut self, sample: Color) {
+        self.sum += sample;
+        self.count += 1;
+        let lum = luminance(&sample);
+        let lum = if lum.is_nan() { 0.0 } else { lum };
+        let delta = lum - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (lum - self.mean);
+    }
+
+    // Chan et al.'s parallel variant of Welford's update
+    fn merge(&mut self, other: &PixelStats) {
+        if other.count == 0 {
+            return;
+        }
+        let (na, nb) = (self.count as f64, other.count as f64);
+        let n = na + nb;
+        let delta = other.mean - self.mean;
+        self.sum += other.sum;
+        self.count += other.count;
+        self.mean += delta * nb / n;
+        self.m2 += other.m2 + delta * delta * na * nb / n;
+    }
+
+    // standard error of the mean, relative to the mean
+    pub fn relative_error(&self) -> f64 {
+        if self.count < 2 {
+            return INFINITY;
+        }
+        let variance = self.m2 / (self.count - 1) as f64;
+        (variance / self.count as f64).sqrt() / self.mean.max(1e-3)
+    }
+}
+
+pub fn luminance(c: &Color) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+pub struct Accum {
+    width: u32,
+    height: u32,
+    // passes already taken, a resumed render continues with the next one
+    passes: u32,
+    pixels: Vec<PixelStats>,
+}
+
+impl Accum {
+    pub fn new(width: u32, height: u32) -> Self {
+        Accum {
+            width,
+            height,
+            passes: 0,
+            pixels: vec![PixelStats::default(); (width * height) as usize],
+        }
+    }
+
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.pixels.iter().map(|p| p.count as u64).sum()
+    }
+
+    fn add_tile(&mut self, tile: &Tile, buf: &[PixelStats]) {
+        for (row, line) in buf.chunks(tile.width as usize).enumerate() {
+            let start = ((tile.y0 + row as u32) * self.width + tile.x0) as usize;
+            for (i, stats) in line.iter().enumerate() {
+                self.pixels[start + i].merge(stats);
+            }
+        }
+    }
+
+    pub fn image(&self) -> Rgb32FImage {
+        Rgb32FImage::from_fn(self.width, self.height, |i, j| {
+            let pixel = &self.pixels[(j * self.width + i) as usize];
+            match pixel.count {
+                0 => linear_pixel(&Color::default()),
+                n => linear_pixel(&(pixel.sum / n as f64)),
+            }
+        })
+    }
+
+    // debug view of adaptive sampling, white is the most sampled pixel
+    pub fn sample_map(&self) -> GrayImage {
+        let max = self
+            .pixels
+            .iter()
+            .map(|p| p.count)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        GrayImage::from_fn(self.width, self.height, |i, j| {
+            let count = self.pixels[(j * self.width + i) as usize].count;
+            Luma([(count as u64 * 255 / max as u64) as u8])
+        })
+    }
+}
+
+// strata are visited in a scrambled order, so that the first passes already
+// cover the whole pixel instead of only its top rows; adaptive sampling may
+// stop a pixel after any pass
+fn stratum(s: u32, sqrt_spp: u32, stride: u32) -> (u32, u32) {
+    let n = sqrt_spp * sqrt_spp;
+    let k = ((s % n) as u64 * stride as u64 % n as u64) as u32;
+    (k % sqrt_spp, k / sqrt_spp)
+}
+
+// a step close to n / golden ratio, coprime to n so every stratum is visited
+fn stratum_stride(n: u32) -> u32 {
+    let mut stride = ((n as f64 * 0.618) as u32).max(1);
+    while gcd(stride, n) != 1 {
+        stride += 1;
+    }
+    stride
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// how the passes are split up and what is written in between
+#[derive(Clone, Debug)]
+pub struct PassParam {
+    pub preview: PreviewParam,
+    pub checkpoint: Option<CheckpointParam>,
+    pub adaptive: Option<AdaptiveParam>,
+    pub tile_size: u32,
+    pub tile_order: TileOrder,
+    // trace every path at one wavelength, for dispersion (util::spectrum)
+    pub spectral: bool,
+}
+
+impl Default for PassParam {
+    fn default() -> Self {
+        PassParam {
+            preview: PreviewParam::default(),
+            checkpoint: None,
+            adaptive: None,
+            tile_size: DEFAULT_TILE_SIZE,
+            tile_order: TileOrder::default(),
+            spectral: false,
+        }
+    }
+}
+
+// `accum` is empty for a fresh render, or the state loaded from a checkpoint.
+// the scene is only borrowed, animations keep it from frame to frame
+pub fn render<F: FnMut(&Rgb32FImage, u32)>(
+    scene: &Scene,
+    render_param: &RenderParam,
+    param: &PassParam,
+    mut accum: Accum,
+    mut on_preview: F,
+) -> Accum {
+    let (world, lights, cam, back_ground) = scene;
+    let image_width = cam.image_width;
+    let image_height = cam.image_height();
+    let pixel_num = (image_height * image_width) as u64;
+    // one pass takes one stratified sample (s_i, s_j) of every pixel
+    let passes = cam.sqrt_spp * cam.sqrt_spp;
+    let stride = stratum_stride(passes);
+    // adaptive sampling may give noisy pixels more passes, as long as the
+    // total number of samples stays within passes * pixels
+    let max_passes = param.adaptive.map_or(passes, |a| a.max_passes(passes));
+    let budget = pixel_num * passes as u64;
+    let batch =
+        if param.preview.is_enabled() || param.checkpoint.is_some() || param.adaptive.is_some() {
+            1
+        } else {
+            passes
+        };
+
+    let progress_bar = if option_env!("CI").unwrap_or_default() == "true" {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(budget)
+    };
+    progress_bar.inc(accum.samples());
+
+    let tiles = tiles(image_width, image_height, param.tile_size, param.tile_order);
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(render_param.thread_num)
+        .build()
+        .expect("Couldn't build the thread pool");
+
+    let render_tile = |tile: &Tile, pass: u32, end: u32, active: &[bool]| {
+        let mut buf = vec![PixelStats::default(); (tile.width * tile.height) as usize];
+        let mut samples = 0;
+        for (k, stats) in buf.iter_mut().enumerate() {
+            let i = tile.x0 + k as u32 % tile.width;
+            let j = tile.y0 + k as u32 / tile.width;
+            if !active.is_empty() && !active[(j * image_width + i) as usize] {
+                continue;
+            }
+            let background_color = back_ground.value(i, j, image_width, image_height);
+            let background_illum = Color::new(0.6, 0.8, 1.0) * 0.5;
+
+            for s in pass..end {
+                random::seed_sample(render_param.seed, (j * image_width + i) as u64, s as u64);
+                let (s_i, s_j) = stratum(s, cam.sqrt_spp, stride);
+                let r = cam.get_ray(i, j, s_i, s_j);
+                let (r, weight) = if param.spectral {
+                    let (wavelength, weight) = spectrum::sample_wavelength();
+                    (r.with_wavelength(wavelength), weight)
+                } else {
+                    (r, Color::new(1.0, 1.0, 1.0))
+                };
+                let color = ray_color(
+                    &r,
+                    cam.max_recurse_depth,
+                    cam.max_recurse_depth,
+                    world,
+                    lights,
+                    background_color,
+                    background_illum,
+                );
+                stats.add(color * weight);
+            }
+            samples += (end - pass) as u64;
+        }
+        progress_bar.inc(samples);
+        buf
+    };
+
+    let mut pass = accum.passes;
+    let mut last_preview = (pass, Instant::now());
+    let mut last_checkpoint = Instant::now();
+    while pass < max_passes {
+        // empty means every pixel is still sampled
+        let active: Vec<bool> = match param.adaptive {
+            Some(adaptive) if pass >= adaptive.min_passes => {
+                let active: Vec<bool> = accum
+                    .pixels
+                    .iter()
+                    .map(|p| !adaptive.is_converged(p))
+                    .collect();
+                if !active.contains(&true) || accum.samples() >= budget {
+                    break;
+                }
+                active
+            }
+            _ => Vec::new(),
+        };
+
+        let end = (pass + batch).min(max_passes);
+        // par_bridge hands the tiles out in order; every tile fills its own
+        // buffer, which are added up afterwards without any locking
+        let done: Vec<(&Tile, Vec<PixelStats>)> = pool.install(|| {
+            tiles
+                .iter()
+                .par_bridge()
+                .map(|tile| (tile, render_tile(tile, pass, end, &active)))
+                .collect()
+        });
+        for (tile, buf) in done {
+            accum.add_tile(tile, &buf);
+        }
+        pass = end;
+        accum.passes = pass;
+
+        if let Some(checkpoint) = &param.checkpoint {
+            if last_checkpoint.elapsed().as_secs_f64() >= checkpoint.every_secs {
+                save_checkpoint(&accum, checkpoint, cam.sqrt_spp);
+                last_checkpoint = Instant::now();
+            }
+        }
+        if pass < max_passes
+            && param
+                .preview
+                .is_due(pass - last_preview.0, last_preview.1.elapsed())
+        {
+            on_preview(&accum.image(), pass);
+            last_preview = (pass, Instant::now());
+        }
+    }
+    // the final state is saved too, so a finished render can be reloaded
+    if let Some(checkpoint) = &param.checkpoint {
+        save_checkpoint(&accum, checkpoint, cam.sqrt_spp);
+    }
+    progress_bar.finish();
+    accum
+}
+
+fn save_checkpoint(accum: &Accum, checkpoint: &CheckpointParam, sqrt_spp: u32) {
+    if let Err(err) = accum.save(&checkpoint.path, sqrt_spp) {
+        eprintln!("\nwarning: cannot write checkpoint: {}", err);
+    }
+}
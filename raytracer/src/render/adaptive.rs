@@ -0,0 +1,37 @@
+// adaptive sampling: pixels whose estimate is already good enough stop
+// taking samples, and their share of the budget goes to the noisy ones
+
+use crate::render::PixelStats;
+
+pub const DEFAULT_MIN_PASSES: u32 = 16;
+// without a limit a noisy pixel may take up to this many times the spp
+pub const DEFAULT_MAX_FACTOR: u32 = 4;
+
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveParam {
+    // stop a pixel once its relative standard error drops below this
+    pub threshold: f64,
+    // passes every pixel gets before its variance is trusted
+    pub min_passes: u32,
+    pub max_spp: Option<u32>,
+}
+
+impl AdaptiveParam {
+    pub fn new(threshold: f64) -> Self {
+        AdaptiveParam {
+            threshold,
+            min_passes: DEFAULT_MIN_PASSES,
+            max_spp: None,
+        }
+    }
+
+    pub fn max_passes(&self, passes: u32) -> u32 {
+        self.max_spp
+            .unwrap_or(passes * DEFAULT_MAX_FACTOR)
+            .max(passes)
+    }
+
+    pub fn is_converged(&self, pixel: &PixelStats) -> bool {
+        pixel.relative_error() < self.threshold
+    }
+}
@@ -0,0 +1,115 @@
+// save / restore the accumulation state, so that a killed render can go on
+// where it stopped instead of starting over
+
+use crate::render::Accum;
+use crate::util::color::Color;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+#[derive(Clone, Debug)]
+pub struct CheckpointParam {
+    pub path: String,
+    pub every_secs: f64,
+    // continue from the state in `path` instead of starting over
+    pub resume: bool,
+}
+
+pub const DEFAULT_CHECKPOINT_SECS: f64 = 60.0;
+
+// file layout, all little-endian:
+//   magic, version, width, height, sqrt_spp, passes   (u32 each after the magic)
+//   then per pixel: sum r, g, b (f64), count (u32), luminance mean, m2 (f64)
+const MAGIC: &[u8; 4] = b"RTCK";
+const VERSION: u32 = 2;
+
+impl Accum {
+    pub fn save(&self, path: &str, sqrt_spp: u32) -> io::Result<()> {
+        // write next to the target first, a kill during the write must not
+        // destroy the previous checkpoint
+        let tmp = format!("{}.tmp", path);
+        if let Some(dir) = Path::new(path).parent() {
+            if !dir.as_os_str().is_empty() {
+                fs::create_dir_all(dir)?;
+            }
+        }
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        writer.write_all(MAGIC)?;
+        for v in [VERSION, self.width, self.height, sqrt_spp, self.passes] {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+        for pixel in self.pixels.iter() {
+            for c in pixel.sum.e {
+                writer.write_all(&c.to_le_bytes())?;
+            }
+            writer.write_all(&pixel.count.to_le_bytes())?;
+            writer.write_all(&pixel.mean.to_le_bytes())?;
+            writer.write_all(&pixel.m2.to_le_bytes())?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&tmp, path)
+    }
+
+    // the checkpoint has to come from a render of the same size and spp,
+    // otherwise the remaining strata would not match up
+    pub fn load(path: &str, width: u32, height: u32, sqrt_spp: u32) -> io::Result<Accum> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a checkpoint file"));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid(&format!(
+                "unsupported checkpoint version {}",
+                version
+            )));
+        }
+        let (w, h) = (read_u32(&mut reader)?, read_u32(&mut reader)?);
+        if (w, h) != (width, height) {
+            return Err(invalid(&format!(
+                "checkpoint is {}x{}, but the image is {}x{}",
+                w, h, width, height
+            )));
+        }
+        let spp = read_u32(&mut reader)?;
+        if spp != sqrt_spp {
+            return Err(invalid(&format!(
+                "checkpoint was made with {} samples per pixel, not {}",
+                spp * spp,
+                sqrt_spp * sqrt_spp
+            )));
+        }
+        let mut accum = Accum::new(width, height);
+        accum.passes = read_u32(&mut reader)?;
+        for pixel in accum.pixels.iter_mut() {
+            pixel.sum = Color::new(
+                read_f64(&mut reader)?,
+                read_f64(&mut reader)?,
+                read_f64(&mut reader)?,
+            );
+            pixel.count = read_u32(&mut reader)?;
+            pixel.mean = read_f64(&mut reader)?;
+            pixel.m2 = read_f64(&mut reader)?;
+        }
+        Ok(accum)
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_f64<R: Read>(reader: &mut R) -> io::Result<f64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(f64::from_le_bytes(buf))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
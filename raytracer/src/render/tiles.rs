@@ -0,0 +1,105 @@
+// split the image into tiles and decide in which order they are handed out
+
+use std::fmt;
+use std::str::FromStr;
+
+pub const DEFAULT_TILE_SIZE: u32 = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub x0: u32,
+    pub y0: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TileOrder {
+    // row by row, top to bottom
+    Scanline,
+    // neighbouring tiles stay close in time, which keeps caches warm
+    #[default]
+    Hilbert,
+    // from the center outwards, so previews show the subject first
+    Spiral,
+}
+
+impl FromStr for TileOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "scanline" => Ok(TileOrder::Scanline),
+            "hilbert" => Ok(TileOrder::Hilbert),
+            "spiral" => Ok(TileOrder::Spiral),
+            _ => Err(format!("unknown tile order '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for TileOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileOrder::Scanline => write!(f, "scanline"),
+            TileOrder::Hilbert => write!(f, "hilbert"),
+            TileOrder::Spiral => write!(f, "spiral"),
+        }
+    }
+}
+
+pub fn tiles(width: u32, height: u32, size: u32, order: TileOrder) -> Vec<Tile> {
+    let (nx, ny) = ((width + size - 1) / size, (height + size - 1) / size);
+    let mut grid: Vec<(u32, u32)> = (0..ny)
+        .flat_map(|ty| (0..nx).map(move |tx| (tx, ty)))
+        .collect();
+
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Hilbert => {
+            let n = nx.max(ny).next_power_of_two();
+            grid.sort_by_key(|&(tx, ty)| hilbert_index(n, tx, ty));
+        }
+        TileOrder::Spiral => {
+            // ring around the center tile first, then the angle within the ring
+            let (cx, cy) = ((nx as f64 - 1.0) / 2.0, (ny as f64 - 1.0) / 2.0);
+            let key = |&(tx, ty): &(u32, u32)| {
+                let (dx, dy) = (tx as f64 - cx, ty as f64 - cy);
+                (dx.abs().max(dy.abs()), dy.atan2(dx))
+            };
+            grid.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+        }
+    }
+
+    grid.into_iter()
+        .map(|(tx, ty)| {
+            let (x0, y0) = (tx * size, ty * size);
+            Tile {
+                x0,
+                y0,
+                width: size.min(width - x0),
+                height: size.min(height - y0),
+            }
+        })
+        .collect()
+}
+
+// distance of (x, y) along the Hilbert curve filling an n x n grid, n a power of two
+fn hilbert_index(n: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = ((x & s) > 0) as u32;
+        let ry = ((y & s) > 0) as u32;
+        d += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+        // rotate the quadrant so the curve stays continuous
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
@@ -3,9 +3,24 @@ pub mod image_texture;
 pub mod noise_texture;
 
 use crate::util::{color::Color, vec3::*};
+use crate::worlds::scene_file::desc::TextureDesc;
+use std::sync::Arc;
 
 pub trait Texture: Send + Sync {
     fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+    // how to write this texture into a scene file, None if it cannot be
+    fn describe(&self) -> Option<TextureDesc> {
+        None
+    }
+}
+
+impl<T: Texture + ?Sized> Texture for Arc<T> {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        (**self).value(u, v, p)
+    }
+    fn describe(&self) -> Option<TextureDesc> {
+        (**self).describe()
+    }
 }
 
 #[derive(Clone)]
@@ -28,4 +43,9 @@ impl Texture for SolidColor {
     fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
         self.albedo
     }
+    fn describe(&self) -> Option<TextureDesc> {
+        Some(TextureDesc::Solid {
+            color: self.albedo.e,
+        })
+    }
 }
@@ -1,6 +1,7 @@
 use crate::{
     textures::{SolidColor, Texture},
     util::{color::Color, vec3::*},
+    worlds::scene_file::desc::TextureDesc,
 };
 
 pub struct CheckerTexture<TO: Texture, TE: Texture> {
@@ -40,4 +41,11 @@ impl<TO: Texture, TE: Texture> Texture for CheckerTexture<TO, TE> {
             self.odd.value(u, v, p)
         }
     }
+    fn describe(&self) -> Option<TextureDesc> {
+        Some(TextureDesc::Checker {
+            scale: 1.0 / self.inv_scale,
+            even: self.even.describe()?.into(),
+            odd: self.odd.describe()?.into(),
+        })
+    }
 }
@@ -1,6 +1,7 @@
 use crate::{
     textures::Texture,
     util::{color::Color, image_process::process_pixels, interval::Interval, vec3::*},
+    worlds::scene_file::desc::TextureDesc,
 };
 use image::RgbImage;
 
@@ -9,6 +10,7 @@ pub struct ImageTexture {
     image_width: u32,
     image_height: u32,
     image_pixels: RgbImage,
+    path: Option<String>, // None if not loaded from a file
 }
 
 impl ImageTexture {
@@ -18,6 +20,7 @@ impl ImageTexture {
             image_width,
             image_height,
             image_pixels,
+            path: Some(String::from(image_path)),
         }
     }
     pub fn _new_image(image_pixels: RgbImage) -> ImageTexture {
@@ -26,6 +29,7 @@ impl ImageTexture {
             image_width,
             image_height,
             image_pixels,
+            path: None,
         }
     }
 }
@@ -46,4 +50,9 @@ impl Texture for ImageTexture {
             color_scale * pixel[2] as f64,
         )
     }
+    fn describe(&self) -> Option<TextureDesc> {
+        Some(TextureDesc::Image {
+            path: self.path.clone()?,
+        })
+    }
 }
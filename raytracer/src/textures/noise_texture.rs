@@ -1,6 +1,7 @@
 use crate::{
     textures::Texture,
     util::{color::Color, perlin::Perlin, vec3::*},
+    worlds::scene_file::desc::TextureDesc,
 };
 
 #[derive(Default, Clone)]
@@ -37,4 +38,11 @@ impl Texture for NoiseTexture {
             Color::new(0.5, 0.5, 0.5) * (1.0 + self.noise.noise(&(p * self.scale)))
         }
     }
+    fn describe(&self) -> Option<TextureDesc> {
+        Some(TextureDesc::Noise {
+            scale: self.scale,
+            turbulence: self.with_turbulence,
+            depth: self.turb_depth,
+        })
+    }
 }
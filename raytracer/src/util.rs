@@ -1,19 +1,27 @@
 pub mod aabb;
 pub mod color;
+pub mod ggx;
+pub mod image_output;
 pub mod image_process;
 pub mod interval;
+pub mod mat4;
 pub mod onb;
 pub mod perlin;
+pub mod random;
 pub mod ray;
+pub mod spectrum;
+pub mod tone_map;
 pub mod vec3;
 
+use crate::hittable::bvh::{BvhLayout, BvhStrategy};
+use crate::util::tone_map::ToneParam;
+
 // some constants
 pub const INFINITY: f64 = f64::INFINITY;
 pub const PI: f64 = std::f64::consts::PI;
-// Image
+// Image (defaults, can be overridden from the command line)
 pub const ASPECT_RATIO: f64 = 16.0 / 9.0;
 pub const IMAGE_WIDTH: u32 = 800;
-pub const IMAGE_HEIGHT: u32 = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as u32;
 pub const SAMPLES_PER_PIXEL: u32 = 225;
 pub const MAX_RECURSE_DEPTH: i32 = 50;
 // Threads
@@ -24,8 +32,40 @@ pub fn degrees_to_radians(degrees: f64) -> f64 {
     degrees * PI / 180.0
 }
 
+// runtime image/sampling settings, handed to every scene builder
+#[derive(Clone)]
+pub struct RenderParam {
+    pub image_width: u32,
+    pub aspect_ratio: f64,
+    pub samples_per_pixel: u32,
+    pub max_recurse_depth: i32,
+    pub thread_num: usize,
+    // drives every random draw, see util::random
+    pub seed: u64,
+    // None leaves the choice to the scene
+    pub bvh: Option<BvhStrategy>,
+    pub bvh_layout: BvhLayout,
+}
+
+impl Default for RenderParam {
+    fn default() -> Self {
+        RenderParam {
+            image_width: IMAGE_WIDTH,
+            aspect_ratio: ASPECT_RATIO,
+            samples_per_pixel: SAMPLES_PER_PIXEL,
+            max_recurse_depth: MAX_RECURSE_DEPTH,
+            thread_num: THREAD_NUM,
+            seed: 0,
+            bvh: None,
+            bvh_layout: BvhLayout::default(),
+        }
+    }
+}
+
 pub struct OutputParam<'a> {
     pub enable_edge_detect: bool,
+    // only used for 8-bit output, float formats stay linear
+    pub tone: ToneParam,
     pub savedir: &'a str,
     pub savefile: &'a str,
 }
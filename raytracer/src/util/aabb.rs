@@ -80,6 +80,14 @@ impl Aabb {
         }
         true
     }
+    pub fn surface_area(&self) -> f64 {
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+    pub fn centroid(&self, n: i32) -> f64 {
+        let ax = self.axis_interval(n);
+        0.5 * (ax.min + ax.max)
+    }
     // The longest axis of the bounding box
     pub fn longest_axis(&self) -> i32 {
         if self.x.size() > self.y.size() {
@@ -98,13 +106,13 @@ impl Aabb {
         // not let any interval too small
         let eps = 0.0001;
         if self.x.size() < eps {
-            self.x.expand(eps);
+            self.x = self.x.expand(eps);
         }
         if self.y.size() < eps {
-            self.y.expand(eps);
+            self.y = self.y.expand(eps);
         }
         if self.z.size() < eps {
-            self.z.expand(eps);
+            self.z = self.z.expand(eps);
         }
     }
 }
@@ -1,17 +1,10 @@
 use super::interval::Interval;
+use super::tone_map::{linear_to_srgb, ToneParam};
 use super::vec3::Vec3;
 
 pub type Color = Vec3;
 
-fn linear_to_gamma(linear_component: f64) -> f64 {
-    if linear_component > 0.0 {
-        linear_component.sqrt()
-    } else {
-        0.0
-    }
-}
-
-pub fn put_color(pixel_color: &Color) -> (i32, i32, i32) {
+pub fn put_color(pixel_color: &Color, tone: &ToneParam) -> (i32, i32, i32) {
     let mut r = pixel_color.x();
     let mut g = pixel_color.y();
     let mut b = pixel_color.z();
@@ -27,10 +20,11 @@ pub fn put_color(pixel_color: &Color) -> (i32, i32, i32) {
         b = 0.0
     }
 
-    // Apply linear to gamma-2 transformation
-    r = linear_to_gamma(r);
-    g = linear_to_gamma(g);
-    b = linear_to_gamma(b);
+    // Exposure and tone mapping, then linear to sRGB
+    let mapped = tone.apply(&Color::new(r, g, b));
+    r = linear_to_srgb(mapped.x());
+    g = linear_to_srgb(mapped.y());
+    b = linear_to_srgb(mapped.z());
 
     // Translation
     static INTENSITY: Interval = Interval {
@@ -0,0 +1,207 @@
+// the GGX / Trowbridge-Reitz microfacet distribution with Smith masking,
+// in a local frame where the surface normal is +z. alpha is the squared
+// perceptual roughness, kept away from 0 where the lobe becomes a mirror
+
+use crate::util::{random::rng, vec3::*, INFINITY, PI};
+use rand::Rng;
+
+pub const MIN_ALPHA: f64 = 1e-3;
+
+pub fn alpha(roughness: f64) -> f64 {
+    (roughness * roughness).clamp(MIN_ALPHA, 1.0)
+}
+
+// density of microfacet normals h
+pub fn d(h: &Vec3, alpha: f64) -> f64 {
+    if h.z() <= 0.0 {
+        return 0.0;
+    }
+    let a2 = alpha * alpha;
+    let t = h.z() * h.z() * (a2 - 1.0) + 1.0;
+    a2 / (PI * t * t)
+}
+
+// Smith's auxiliary function, the masking of v is 1 / (1 + lambda)
+pub fn lambda(v: &Vec3, alpha: f64) -> f64 {
+    let cos2 = v.z() * v.z();
+    if cos2 <= 0.0 {
+        return INFINITY;
+    }
+    let tan2 = (1.0 - cos2).max(0.0) / cos2;
+    ((1.0 + alpha * alpha * tan2).sqrt() - 1.0) / 2.0
+}
+
+pub fn g1(v: &Vec3, alpha: f64) -> f64 {
+    1.0 / (1.0 + lambda(v, alpha))
+}
+
+// height correlated masking and shadowing of the pair
+pub fn g2(wo: &Vec3, wi: &Vec3, alpha: f64) -> f64 {
+    1.0 / (1.0 + lambda(wo, alpha) + lambda(wi, alpha))
+}
+
+pub fn schlick(f0: f64, cos: f64) -> f64 {
+    f0 + (1.0 - f0) * (1.0 - cos).clamp(0.0, 1.0).powi(5)
+}
+
+// a microfacet normal as seen from wo (Heitz 2018, "Sampling the GGX
+// distribution of visible normals"), wo in the upper hemisphere
+pub fn sample_visible_normal(wo: &Vec3, alpha: f64) -> Vec3 {
+    let mut rng = rng();
+    // stretch to the hemisphere configuration
+    let vh = unit_vector(&Vec3::new(alpha * wo.x(), alpha * wo.y(), wo.z()));
+    let lensq = vh.x() * vh.x() + vh.y() * vh.y();
+    let t1 = if lensq > 0.0 {
+        Vec3::new(-vh.y(), vh.x(), 0.0) / lensq.sqrt()
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let t2 = cross(&vh, &t1);
+    // a point on the projected disk, squeezed towards the visible half
+    let r = rng.gen_range(0.0..1.0f64).sqrt();
+    let phi = 2.0 * PI * rng.gen_range(0.0..1.0);
+    let p1 = r * phi.cos();
+    let s = 0.5 * (1.0 + vh.z());
+    let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+    let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+    // and back
+    unit_vector(&Vec3::new(alpha * nh.x(), alpha * nh.y(), nh.z().max(1e-6)))
+}
+
+// density of visible normals from wo
+pub fn visible_normal_pdf(wo: &Vec3, h: &Vec3, alpha: f64) -> f64 {
+    if wo.z() <= 0.0 {
+        return 0.0;
+    }
+    g1(wo, alpha) * dot(wo, h).max(0.0) * d(h, alpha) / wo.z()
+}
+
+// density of wi = reflect(-wo, h) with h drawn by sample_visible_normal
+pub fn reflection_pdf(wo: &Vec3, wi: &Vec3, alpha: f64) -> f64 {
+    let h = *wo + *wi;
+    if h.length_squared() <= 0.0 {
+        return 0.0;
+    }
+    let h = unit_vector(&h);
+    let cos = dot(wo, &h);
+    if cos <= 0.0 {
+        return 0.0;
+    }
+    visible_normal_pdf(wo, &h, alpha) / (4.0 * cos)
+}
+
+// unpolarized Fresnel reflectance of a dielectric boundary, cos_i measured
+// on the side of wo and eta the index below over the index above
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let (cos_i, eta) = if cos_i < 0.0 {
+        (-cos_i, 1.0 / eta)
+    } else {
+        (cos_i.min(1.0), eta)
+    };
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        // total internal reflection
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parl * r_parl + r_perp * r_perp) / 2.0
+}
+
+// wo bent through a microfacet h on its side, None past the critical angle
+pub fn refract_through(wo: &Vec3, h: &Vec3, eta: f64) -> Option<Vec3> {
+    let cos_i = dot(wo, h);
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-*wo / eta + *h * (cos_i / eta - cos_t))
+}
+
+// a rough dielectric boundary (Walter et al. 2007) with relative index eta:
+// a visible normal is drawn, then the ray reflects off it with the Fresnel
+// probability and is refracted otherwise
+pub fn sample_dielectric(wo: &Vec3, alpha: f64, eta: f64) -> Vec3 {
+    let h = sample_visible_normal(wo, alpha);
+    let f = fresnel_dielectric(dot(wo, &h), eta);
+    if rng().gen_range(0.0..1.0) < f {
+        return reflect(&-*wo, &h);
+    }
+    refract_through(wo, &h, eta).unwrap_or_else(|| reflect(&-*wo, &h))
+}
+
+// the rough dielectric at wi: (brdf or btdf times |cos wi|, density of
+// sample_dielectric). light crossing the boundary is scaled by 1 / eta^2,
+// the way radiance is squeezed into the denser side. a reflection off a
+// steep facet can end up below the surface and a refraction above it, the
+// density counts both ways of reaching wi so it matches the sampling
+pub fn dielectric(wo: &Vec3, wi: &Vec3, alpha: f64, eta: f64) -> (f64, f64) {
+    if wo.z() <= 0.0 || wi.z() == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (mut value, mut pdf) = (0.0, 0.0);
+
+    let reflected = reflection_pdf(wo, wi, alpha);
+    if reflected > 0.0 {
+        let h = unit_vector(&(*wo + *wi));
+        let f = fresnel_dielectric(dot(wo, &h), eta);
+        pdf += reflected * f;
+        if wi.z() > 0.0 {
+            value = f * d(&h, alpha) * g2(wo, wi, alpha) / (4.0 * wo.z());
+        }
+    }
+
+    let h = *wi * eta + *wo;
+    if h.length_squared() > 0.0 {
+        let h = unit_vector(&h);
+        let h = if h.z() < 0.0 { -h } else { h };
+        let (cos_o, cos_i) = (dot(wo, &h), dot(wi, &h));
+        // wo and wi have to sit on either side of the facet
+        if cos_o > 0.0 && cos_i < 0.0 {
+            let f = fresnel_dielectric(cos_o, eta);
+            let dwh_dwi = -cos_i / (cos_i + cos_o / eta).powi(2);
+            pdf += visible_normal_pdf(wo, &h, alpha) * dwh_dwi * (1.0 - f);
+            if wi.z() < 0.0 {
+                value = (1.0 - f) * d(&h, alpha) * g2(wo, wi, alpha) * cos_o * dwh_dwi
+                    / (wo.z() * eta * eta);
+            }
+        }
+    }
+    (value, pdf)
+}
+
+// Burley's GTR1 with gamma 1 (the Berry distribution), the long tailed lobe
+// of a clearcoat
+pub fn gtr1(h: &Vec3, alpha: f64) -> f64 {
+    if h.z() <= 0.0 {
+        return 0.0;
+    }
+    let a2 = alpha * alpha;
+    (a2 - 1.0) / (PI * a2.ln() * (1.0 + (a2 - 1.0) * h.z() * h.z()))
+}
+
+// a GTR1 normal with density gtr1(h) * h.z
+pub fn sample_gtr1(alpha: f64) -> Vec3 {
+    let mut rng = rng();
+    let a2 = alpha * alpha;
+    let cos2 = (1.0 - a2.powf(1.0 - rng.gen_range(0.0..1.0))) / (1.0 - a2);
+    let cos = cos2.clamp(0.0, 1.0).sqrt();
+    let sin = (1.0 - cos * cos).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.gen_range(0.0..1.0);
+    Vec3::new(sin * phi.cos(), sin * phi.sin(), cos)
+}
+
+// density of wi = reflect(-wo, h) with h drawn by sample_gtr1. unlike
+// visible normals these may face away from wo, the halfway vector then
+// points down and is turned around
+pub fn gtr1_reflection_pdf(wo: &Vec3, wi: &Vec3, alpha: f64) -> f64 {
+    let h = *wo + *wi;
+    if h.length_squared() <= 0.0 {
+        return 0.0;
+    }
+    let h = unit_vector(&h);
+    let h = if h.z() < 0.0 { -h } else { h };
+    gtr1(&h, alpha) * h.z() / (4.0 * dot(wo, &h).abs())
+}
@@ -0,0 +1,87 @@
+// writing the rendered radiance buffer, either quantized to 8 bits or as
+// linear 32-bit floats for later exposure / tone mapping
+
+use crate::util::color::{put_color, Color};
+use crate::util::tone_map::ToneParam;
+use image::codecs::hdr::HdrEncoder;
+use image::{ImageResult, Rgb, Rgb32FImage, RgbImage};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    // png, jpg, ... anything the image crate writes as 8-bit
+    Ldr,
+    Exr,
+    Hdr,
+    Pfm,
+}
+
+impl OutputFormat {
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "exr" => OutputFormat::Exr,
+            "hdr" => OutputFormat::Hdr,
+            "pfm" => OutputFormat::Pfm,
+            _ => OutputFormat::Ldr,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        *self != OutputFormat::Ldr
+    }
+}
+
+// NaN samples would poison every later filter, so they become black here
+pub fn linear_pixel(pixel_color: &Color) -> Rgb<f32> {
+    let channel = |c: f64| if c.is_nan() { 0.0 } else { c as f32 };
+    Rgb([
+        channel(pixel_color.x()),
+        channel(pixel_color.y()),
+        channel(pixel_color.z()),
+    ])
+}
+
+pub fn to_ldr(img: &Rgb32FImage, tone: &ToneParam) -> RgbImage {
+    RgbImage::from_fn(img.width(), img.height(), |i, j| {
+        let Rgb([r, g, b]) = *img.get_pixel(i, j);
+        let (r, g, b) = put_color(&Color::new(r as f64, g as f64, b as f64), tone);
+        Rgb([r as u8, g as u8, b as u8])
+    })
+}
+
+pub fn save_float(img: &Rgb32FImage, path: &Path, format: OutputFormat) -> ImageResult<()> {
+    match format {
+        OutputFormat::Exr => img.save(path),
+        OutputFormat::Hdr => {
+            let writer = BufWriter::new(File::create(path)?);
+            let pixels: Vec<Rgb<f32>> = img.pixels().copied().collect();
+            HdrEncoder::new(writer).encode(&pixels, img.width() as usize, img.height() as usize)
+        }
+        OutputFormat::Pfm => save_pfm(img, path),
+        OutputFormat::Ldr => to_ldr(img, &ToneParam::default()).save(path),
+    }
+}
+
+// the image crate has no PFM encoder, the format is simple enough:
+// a text header, then little-endian floats with the bottom row first
+fn save_pfm(img: &Rgb32FImage, path: &Path) -> ImageResult<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    // a negative scale marks little-endian data
+    write!(writer, "PF\n{} {}\n-1.0\n", img.width(), img.height())?;
+    for row in img.rows().rev() {
+        for pixel in row {
+            for c in pixel.0 {
+                writer.write_all(&c.to_le_bytes())?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -1,22 +1,31 @@
 use image::{GenericImageView, RgbImage};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+// image paths are looked up relative to these directories, first match wins
+const SEARCH_PATHS: [&str; 7] = [
+    ".",
+    "source",
+    "../source",
+    "../..",
+    "../../source",
+    "../../../..",
+    "../../../source",
+];
+
+pub fn locate_image(image_path: &str) -> Option<PathBuf> {
+    SEARCH_PATHS
+        .iter()
+        .map(|path| Path::new(path).join(image_path))
+        .find(|fullpath| fullpath.is_file())
+}
+
 pub fn process_pixels(image_path: &str) -> (u32, u32, RgbImage) {
-    let image_path = image_path.to_string();
-    let search_paths = [
-        ".",
-        "source",
-        "../source",
-        "../..",
-        "../../source",
-        "../../../..",
-        "../../../source",
-    ];
     let mut found = false;
     let mut width: u32 = 100;
     let mut height: u32 = 100;
     let mut pixels = image::ImageBuffer::new(width, height);
-    for path in search_paths.iter() {
-        let fullpath = Path::new(path).join(&image_path);
+    for path in SEARCH_PATHS.iter() {
+        let fullpath = Path::new(path).join(image_path);
         if let Ok(img) = image::open(&fullpath) {
             (width, height) = img.dimensions();
             // use par_chunk to ensure each thread process 3 u8 value (1 full pixel)
@@ -0,0 +1,255 @@
+// column-major 4x4 affine matrices, the way glTF stores them: the last row
+// is always 0 0 0 1, so only the upper 3x3 and the translation matter
+
+use crate::util::{
+    degrees_to_radians,
+    vec3::{unit_vector, Point3, Vec3},
+};
+
+#[derive(Clone, Copy)]
+pub struct Mat4 {
+    c: [[f64; 4]; 4], // c[column][row]
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4 {
+        c: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn from_cols(m: [f64; 16]) -> Mat4 {
+        let mut c = [[0.0; 4]; 4];
+        for (i, x) in m.into_iter().enumerate() {
+            c[i / 4][i % 4] = x;
+        }
+        Mat4 { c }
+    }
+
+    pub fn to_cols(self) -> [f64; 16] {
+        std::array::from_fn(|i| self.c[i / 4][i % 4])
+    }
+
+    // translation * rotation (unit quaternion x, y, z, w) * scale
+    pub fn from_trs(t: [f64; 3], r: [f64; 4], s: [f64; 3]) -> Mat4 {
+        let [x, y, z, w] = r;
+        let rot = [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y + z * w),
+                2.0 * (x * z - y * w),
+            ],
+            [
+                2.0 * (x * y - z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z + x * w),
+            ],
+            [
+                2.0 * (x * z + y * w),
+                2.0 * (y * z - x * w),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ];
+        let mut c = Self::IDENTITY.c;
+        for col in 0..3 {
+            for row in 0..3 {
+                c[col][row] = rot[col][row] * s[col];
+            }
+            c[3][col] = t[col];
+        }
+        Mat4 { c }
+    }
+
+    pub fn translation(offset: Vec3) -> Mat4 {
+        let mut ret = Self::IDENTITY;
+        ret.c[3][..3].copy_from_slice(&offset.e);
+        ret
+    }
+
+    pub fn scaling(s: Vec3) -> Mat4 {
+        let mut ret = Self::IDENTITY;
+        for i in 0..3 {
+            ret.c[i][i] = s.e[i];
+        }
+        ret
+    }
+
+    // `angle` degrees by the right-hand rule, around y the same as RotateY
+    pub fn rotation(axis: Vec3, angle: f64) -> Mat4 {
+        let a = unit_vector(&axis);
+        let (sin, cos) = degrees_to_radians(angle).sin_cos();
+        // cos * I + sin * [a]x + (1 - cos) * a a^T
+        let cross = [
+            [0.0, a.z(), -a.y()],
+            [-a.z(), 0.0, a.x()],
+            [a.y(), -a.x(), 0.0],
+        ];
+        let mut ret = Self::IDENTITY;
+        for (col, cross) in cross.iter().enumerate() {
+            for (row, cross) in cross.iter().enumerate() {
+                let diagonal = if col == row { cos } else { 0.0 };
+                ret.c[col][row] = diagonal + sin * cross + (1.0 - cos) * a.e[row] * a.e[col];
+            }
+        }
+        ret
+    }
+
+    // (degrees, offset) when this only turns around y and then moves, the
+    // way RotateY and Translate do
+    pub fn as_rotate_y(&self) -> Option<(f64, Vec3)> {
+        let angle = self.c[2][0].atan2(self.c[0][0]).to_degrees();
+        let turn = Self::rotation(Vec3::new(0.0, 1.0, 0.0), angle);
+        let same =
+            (0..3).all(|col| (0..3).all(|row| (self.c[col][row] - turn.c[col][row]).abs() < 1e-9));
+        same.then(|| (angle, Vec3::new(self.c[3][0], self.c[3][1], self.c[3][2])))
+    }
+
+    // self after rhs, i.e. rhs is applied first
+    pub fn mul(&self, rhs: &Mat4) -> Mat4 {
+        let mut c = [[0.0; 4]; 4];
+        for (col, out) in c.iter_mut().enumerate() {
+            for (row, x) in out.iter_mut().enumerate() {
+                *x = (0..4).map(|k| self.c[k][row] * rhs.c[col][k]).sum();
+            }
+        }
+        Mat4 { c }
+    }
+
+    pub fn point(&self, p: &Point3) -> Point3 {
+        self.vector(p) + Vec3::new(self.c[3][0], self.c[3][1], self.c[3][2])
+    }
+
+    pub fn vector(&self, v: &Vec3) -> Vec3 {
+        let row = |r: usize| self.c[0][r] * v.x() + self.c[1][r] * v.y() + self.c[2][r] * v.z();
+        Vec3::new(row(0), row(1), row(2))
+    }
+
+    // by the transposed upper 3x3; on the inverse, that is how normals go
+    pub fn transpose_vector(&self, v: &Vec3) -> Vec3 {
+        let row = |r: usize| self.c[r][0] * v.x() + self.c[r][1] * v.y() + self.c[r][2] * v.z();
+        Vec3::new(row(0), row(1), row(2))
+    }
+
+    // of the upper 3x3, negative when the transform mirrors
+    pub fn determinant(&self) -> f64 {
+        (0..3)
+            .map(|col| self.c[col][0] * self.cofactor(col, 0))
+            .sum()
+    }
+
+    // None for a singular matrix, e.g. one scaling an axis to 0
+    pub fn inverse(&self) -> Option<Mat4> {
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        // the inverse of the 3x3 is its adjugate over the determinant
+        let mut ret = Self::IDENTITY;
+        for col in 0..3 {
+            for row in 0..3 {
+                ret.c[col][row] = self.cofactor(row, col) / det;
+            }
+        }
+        let t = Vec3::new(self.c[3][0], self.c[3][1], self.c[3][2]);
+        let t = -ret.vector(&t);
+        ret.c[3][..3].copy_from_slice(&t.e);
+        Some(ret)
+    }
+
+    // of the upper 3x3 at (col, row), sign included
+    fn cofactor(&self, col: usize, row: usize) -> f64 {
+        let m = |col: usize, row: usize| self.c[col % 3][row % 3];
+        m(col + 1, row + 1) * m(col + 2, row + 2) - m(col + 2, row + 1) * m(col + 1, row + 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::instances::sphere::Sphere;
+    use crate::hittable::transforms::rotate_y::RotateY;
+    use crate::hittable::transforms::transform::{transform_box, Transform};
+    use crate::hittable::Hittable;
+    use crate::materials::lambertian::Lambertian;
+    use crate::textures::SolidColor;
+    use crate::util::{aabb::Aabb, color::Color, interval::Interval, ray::Ray};
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-9
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let m = Mat4::translation(Vec3::new(1.0, -2.0, 3.0))
+            .mul(&Mat4::rotation(Vec3::new(1.0, 2.0, 0.5), 37.0))
+            .mul(&Mat4::scaling(Vec3::new(2.0, 0.5, -3.0)));
+        let inv = m.inverse().unwrap();
+        let p = Point3::new(0.3, -1.7, 4.2);
+        assert!(close(inv.point(&m.point(&p)), p));
+        assert!(close(m.point(&inv.point(&p)), p));
+        assert!((m.determinant() * inv.determinant() - 1.0).abs() < 1e-9);
+
+        assert!(Mat4::scaling(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn from_trs_scales_then_rotates_then_moves() {
+        // a quarter turn around z
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        let m = Mat4::from_trs([1.0, 2.0, 3.0], [0.0, 0.0, half, half], [2.0, 1.0, 1.0]);
+        assert!(close(
+            m.point(&Point3::new(1.0, 0.0, 0.0)),
+            Point3::new(1.0, 4.0, 3.0)
+        ));
+        let expected = Mat4::translation(Vec3::new(1.0, 2.0, 3.0))
+            .mul(&Mat4::rotation(Vec3::new(0.0, 0.0, 1.0), 90.0))
+            .mul(&Mat4::scaling(Vec3::new(2.0, 1.0, 1.0)));
+        let p = Point3::new(0.5, -1.0, 2.0);
+        assert!(close(m.point(&p), expected.point(&p)));
+    }
+
+    #[test]
+    fn rotation_turns_like_rotate_y() {
+        let mat = Lambertian::<SolidColor>::new_color(Color::new(0.5, 0.5, 0.5));
+        let sphere = || Sphere::new_static(Point3::new(2.0, 0.0, 0.0), 0.5, mat.clone());
+        for angle in [30.0, 90.0, -135.0] {
+            let rotate_y = RotateY::new(sphere(), angle);
+            let transform =
+                Transform::new(sphere(), Mat4::rotation(Vec3::new(0.0, 1.0, 0.0), angle));
+            // aimed at where RotateY takes the center
+            let (sin, cos) = degrees_to_radians(angle).sin_cos();
+            let center = Point3::new(2.0 * cos, 0.0, -2.0 * sin);
+            let r = Ray::new(
+                Point3::new(0.0, 5.0, 0.0),
+                center - Point3::new(0.0, 5.0, 0.0),
+                0.0,
+            );
+            let ray_t = Interval::new(0.001, f64::INFINITY);
+            let (mut a, mut b) = (None, None);
+            assert!(rotate_y.hit(&r, &ray_t, &mut a));
+            assert!(transform.hit(&r, &ray_t, &mut b));
+            let (a, b) = (a.unwrap(), b.unwrap());
+            assert!((a.t - b.t).abs() < 1e-9);
+            assert!(close(a.p, b.p));
+            assert!(close(a.normal, b.normal));
+        }
+    }
+
+    #[test]
+    fn transform_box_bounds_the_moved_corners() {
+        let bbox = Aabb::new_diagonal(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        let m = Mat4::translation(Vec3::new(5.0, 0.0, 0.0))
+            .mul(&Mat4::rotation(Vec3::new(0.0, 0.0, 1.0), 45.0));
+        let moved = transform_box(&bbox, &m);
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        let expect = [(5.0 - half, 5.0 + half), (0.0, 2.0 * half), (0.0, 1.0)];
+        for (axis, (min, max)) in expect.into_iter().enumerate() {
+            let interval = moved.axis_interval(axis as i32);
+            assert!((interval.min - min).abs() < 1e-9);
+            assert!((interval.max - max).abs() < 1e-9);
+        }
+    }
+}
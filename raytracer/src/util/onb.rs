@@ -1,6 +1,6 @@
 // orthonormal basis
 
-use super::vec3::{cross, unit_vector, Vec3};
+use super::vec3::{cross, dot, unit_vector, Vec3};
 
 #[derive(Default)]
 pub struct Onb {
@@ -37,4 +37,8 @@ impl Onb {
     pub fn local_vec(&self, a: &Vec3) -> Vec3 {
         self.u() * a.x() + self.v() * a.y() + self.w() * a.z()
     }
+    // the other way round: a world vector in this basis
+    pub fn local_coords(&self, a: &Vec3) -> Vec3 {
+        Vec3::new(dot(a, self.u()), dot(a, self.v()), dot(a, self.w()))
+    }
 }
@@ -1,5 +1,6 @@
 // perlin noise by hashing
 
+use super::random::rng;
 use super::vec3::{dot, Point3, Vec3};
 use rand::Rng;
 
@@ -89,7 +90,7 @@ impl Perlin {
 }
 
 fn permute(p: &mut [i32], n: i32) {
-    let mut rng = rand::thread_rng();
+    let mut rng = rng();
     for i in (1..n).rev() {
         let target = rng.gen_range(0..=i);
         p.swap(i as usize, target as usize);
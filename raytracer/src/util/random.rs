@@ -0,0 +1,91 @@
+// the random number generator everything draws from: a xoshiro256++ per
+// thread, seeded once before the scene is built and again from
+// (seed, pixel, sample) before every camera sample, so a render repeats
+// exactly whatever the thread count or tile order
+
+use rand::{Error, RngCore};
+use std::cell::RefCell;
+
+struct Xoshiro256 {
+    s: [u64; 4],
+}
+
+impl Xoshiro256 {
+    fn new(seed: u64) -> Self {
+        // splitmix64 spreads the seed over the whole state, as its authors recommend
+        let mut state = seed;
+        let mut s = [0; 4];
+        for x in s.iter_mut() {
+            *x = splitmix64(&mut state);
+        }
+        Xoshiro256 { s }
+    }
+
+    fn next(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+thread_local! {
+    static GENERATOR: RefCell<Xoshiro256> = RefCell::new(Xoshiro256::new(0));
+}
+
+// restart this thread's sequence
+pub fn seed(seed: u64) {
+    GENERATOR.with(|g| *g.borrow_mut() = Xoshiro256::new(seed));
+}
+
+// the sequence of one camera sample only depends on where and which it is
+pub fn seed_sample(seed: u64, pixel: u64, sample: u64) {
+    let mut state = seed;
+    let mut state = splitmix64(&mut state) ^ pixel;
+    let mut state = splitmix64(&mut state) ^ sample;
+    self::seed(splitmix64(&mut state));
+}
+
+// handle to this thread's generator, works wherever rand wants an Rng
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalRng;
+
+pub fn rng() -> LocalRng {
+    LocalRng
+}
+
+impl RngCore for LocalRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        GENERATOR.with(|g| g.borrow_mut().next())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
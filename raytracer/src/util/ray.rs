@@ -5,6 +5,8 @@ pub struct Ray {
     orig: Point3,
     dir: Vec3,
     tm: f64,
+    // in nm, 0 for a ray that carries all of rgb, see util::spectrum
+    wavelength: f64,
 }
 
 impl Clone for Ray {
@@ -13,6 +15,7 @@ impl Clone for Ray {
             orig: self.orig,
             dir: self.dir,
             tm: self.tm,
+            wavelength: self.wavelength,
         }
     }
 }
@@ -23,6 +26,7 @@ impl Ray {
             orig: origin,
             dir: direction,
             tm,
+            wavelength: 0.0,
         }
     }
     pub fn _new_0(origin: Point3, direction: Vec3) -> Self {
@@ -30,8 +34,13 @@ impl Ray {
             orig: origin,
             dir: direction,
             tm: 0.0,
+            wavelength: 0.0,
         }
     }
+    pub fn with_wavelength(mut self, wavelength: f64) -> Self {
+        self.wavelength = wavelength;
+        self
+    }
     pub fn origin(&self) -> &Point3 {
         &self.orig
     }
@@ -41,6 +50,9 @@ impl Ray {
     pub fn time(&self) -> f64 {
         self.tm
     }
+    pub fn wavelength(&self) -> f64 {
+        self.wavelength
+    }
 
     // at time t, where is it (dir is the speed)
     pub fn at(&self, t: f64) -> Point3 {
@@ -0,0 +1,146 @@
+// spectral rendering: a path may carry one wavelength, which dispersive
+// glass bends by. the radiance it brings back is still the scene's rgb and
+// is weighed by what that wavelength adds to each channel, the CIE color
+// matching functions taken to linear sRGB. each channel's weights add up
+// to one, so anything that does not depend on the wavelength comes out as
+// it would without a wavelength, just noisier
+
+use crate::util::{color::Color, random::rng};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+// the visible range in nm
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 780.0;
+// the helium d line, where glass catalogs quote the index of refraction
+pub const D_LINE: f64 = 587.6;
+
+// CIE 1931 standard observer, the multi-lobe fit of Wyman, Sloan and
+// Shirley 2013
+pub fn cie_xyz(lambda: f64) -> [f64; 3] {
+    let g = |mu: f64, s1: f64, s2: f64| {
+        let t = (lambda - mu) / if lambda < mu { s1 } else { s2 };
+        (-0.5 * t * t).exp()
+    };
+    [
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    ]
+}
+
+pub fn xyz_to_linear_srgb([x, y, z]: [f64; 3]) -> Color {
+    Color::new(
+        3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+        -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+        0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+    )
+}
+
+// the channel weights per 1 nm bin, with the negative lobes of the red
+// and blue curves cut off, and the cdf of their mean that wavelengths are
+// drawn from
+struct Table {
+    weights: Vec<Color>,
+    cdf: Vec<f64>,
+}
+
+fn table() -> &'static Table {
+    static TABLE: OnceLock<Table> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let bins = (LAMBDA_MAX - LAMBDA_MIN) as usize;
+        let mut weights: Vec<Color> = (0..bins)
+            .map(|i| {
+                let c = xyz_to_linear_srgb(cie_xyz(LAMBDA_MIN + i as f64 + 0.5));
+                Color::new(c.x().max(0.0), c.y().max(0.0), c.z().max(0.0))
+            })
+            .collect();
+        let sum = weights.iter().fold(Color::default(), |acc, w| acc + *w);
+        for w in weights.iter_mut() {
+            *w = Color::new(w.x() / sum.x(), w.y() / sum.y(), w.z() / sum.z());
+        }
+        let mut total = 0.0;
+        let cdf = weights
+            .iter()
+            .map(|w| {
+                total += (w.x() + w.y() + w.z()) / 3.0;
+                total
+            })
+            .collect();
+        Table { weights, cdf }
+    })
+}
+
+// a wavelength and what the rgb radiance brought back along it is
+// multiplied by, the channel weights over the density it was drawn with
+pub fn sample_wavelength() -> (f64, Color) {
+    let t = table();
+    let mut rng = rng();
+    let u = rng.gen_range(0.0..1.0) * t.cdf[t.cdf.len() - 1];
+    let bin = t.cdf.partition_point(|&c| c <= u).min(t.cdf.len() - 1);
+    let w = t.weights[bin];
+    let pdf = (w.x() + w.y() + w.z()) / 3.0;
+    (LAMBDA_MIN + bin as f64 + rng.gen_range(0.0..1.0), w / pdf)
+}
+
+// how the index of refraction of a glass changes with the wavelength,
+// coefficients for lambda in micrometers
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Dispersion {
+    // n = a + b / lambda^2
+    Cauchy { a: f64, b: f64 },
+    // n^2 = 1 + sum of b lambda^2 / (lambda^2 - c)
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    // Schott N-BK7, the common crown glass
+    pub const BK7: Dispersion = Dispersion::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+    // Schott N-SF11, a dense flint that splits light about twice as much
+    pub const SF11: Dispersion = Dispersion::Sellmeier {
+        b: [1.73759695, 0.313747346, 1.89878101],
+        c: [0.013188707, 0.0623068142, 155.23629],
+    };
+
+    // lambda in nm
+    pub fn ior(&self, lambda: f64) -> f64 {
+        let l2 = (lambda / 1000.0).powi(2);
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let n2 = 1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f64>();
+                n2.max(0.0).sqrt()
+            }
+        }
+    }
+}
+
+// the linear sRGB color of a black body at `kelvin`, scaled to a luminance
+// of 1 so it only tints whatever intensity a light is given. much below
+// 1000 K the visible glow is too faint to give a color
+pub fn blackbody(kelvin: f64) -> Color {
+    let kelvin = kelvin.max(500.0);
+    // Planck's law up to a constant, lambda in m
+    const HC_OVER_K: f64 = 1.438_776_9e-2;
+    let planck = |lambda: f64| {
+        let l = lambda * 1e-9;
+        1.0 / (l.powi(5) * ((HC_OVER_K / (l * kelvin)).exp() - 1.0))
+    };
+    let mut xyz = [0.0; 3];
+    let mut lambda = LAMBDA_MIN + 0.5;
+    while lambda < LAMBDA_MAX {
+        let b = planck(lambda);
+        for (v, m) in xyz.iter_mut().zip(cie_xyz(lambda)) {
+            *v += b * m;
+        }
+        lambda += 1.0;
+    }
+    let y = xyz[1];
+    let c = xyz_to_linear_srgb(xyz.map(|v| v / y));
+    Color::new(c.x().max(0.0), c.y().max(0.0), c.z().max(0.0))
+}
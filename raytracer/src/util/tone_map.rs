@@ -0,0 +1,121 @@
+// map linear radiance into [0, 1] before the sRGB transfer and quantization,
+// so that bright emitters roll off instead of clipping
+
+use crate::util::color::Color;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ToneMap {
+    // plain clamp to [0, 1], the old behaviour
+    #[default]
+    Clamp,
+    Reinhard,
+    // radiance at `white` (and above) maps to 1
+    ReinhardExtended {
+        white: f64,
+    },
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+    // Uncharted 2 filmic curve
+    Hable,
+}
+
+const DEFAULT_WHITE: f64 = 4.0;
+
+impl ToneMap {
+    pub fn map(&self, c: f64) -> f64 {
+        let c = c.max(0.0);
+        match *self {
+            ToneMap::Clamp => c.min(1.0),
+            ToneMap::Reinhard => c / (1.0 + c),
+            ToneMap::ReinhardExtended { white } => c * (1.0 + c / (white * white)) / (1.0 + c),
+            ToneMap::Aces => {
+                let (a, b, cc, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                (c * (a * c + b)) / (c * (cc * c + d) + e)
+            }
+            ToneMap::Hable => {
+                const EXPOSURE_BIAS: f64 = 2.0;
+                const WHITE: f64 = 11.2;
+                hable_partial(c * EXPOSURE_BIAS) / hable_partial(WHITE)
+            }
+        }
+        .clamp(0.0, 1.0)
+    }
+}
+
+fn hable_partial(x: f64) -> f64 {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
+
+// "reinhard", "reinhard:6.0" (extended, with white point), "aces", "hable", "clamp"
+impl FromStr for ToneMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+        let tone_map = match (name.to_ascii_lowercase().as_str(), arg) {
+            ("clamp" | "none", None) => ToneMap::Clamp,
+            ("reinhard", None) => ToneMap::Reinhard,
+            ("reinhard", Some(white)) | ("reinhard-extended", Some(white)) => {
+                let white: f64 = white
+                    .parse()
+                    .map_err(|_| format!("invalid white point '{}'", white))?;
+                if !white.is_finite() || white <= 0.0 {
+                    return Err(format!("invalid white point '{}'", white));
+                }
+                ToneMap::ReinhardExtended { white }
+            }
+            ("reinhard-extended", None) => ToneMap::ReinhardExtended {
+                white: DEFAULT_WHITE,
+            },
+            ("aces", None) => ToneMap::Aces,
+            ("hable" | "uncharted2", None) => ToneMap::Hable,
+            _ => return Err(format!("unknown tone map '{}'", s)),
+        };
+        Ok(tone_map)
+    }
+}
+
+impl fmt::Display for ToneMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToneMap::Clamp => write!(f, "clamp"),
+            ToneMap::Reinhard => write!(f, "reinhard"),
+            ToneMap::ReinhardExtended { white } => write!(f, "reinhard:{}", white),
+            ToneMap::Aces => write!(f, "aces"),
+            ToneMap::Hable => write!(f, "hable"),
+        }
+    }
+}
+
+// everything applied between the linear buffer and 8-bit sRGB
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ToneParam {
+    pub tone_map: ToneMap,
+    // in stops, every +1 doubles the radiance
+    pub exposure: f64,
+}
+
+impl ToneParam {
+    pub fn apply(&self, pixel_color: &Color) -> Color {
+        let scale = 2f64.powf(self.exposure);
+        Color::new(
+            self.tone_map.map(pixel_color.x() * scale),
+            self.tone_map.map(pixel_color.y() * scale),
+            self.tone_map.map(pixel_color.z() * scale),
+        )
+    }
+}
+
+pub fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
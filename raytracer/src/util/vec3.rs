@@ -1,3 +1,4 @@
+use super::random::rng;
 use super::PI;
 use rand::Rng;
 use std::f64;
@@ -220,7 +221,7 @@ impl Vec3 {
         self.e[0].abs() < epsilon && self.e[1].abs() < epsilon && self.e[2].abs() < epsilon
     }
     pub fn random_in(min: f64, max: f64) -> Self {
-        let mut rng = rand::thread_rng();
+        let mut rng = rng();
         Self {
             e: [
                 rng.gen_range(min..max),
@@ -230,11 +231,12 @@ impl Vec3 {
         }
     }
     pub fn random() -> Self {
+        let mut rng = rng();
         Self {
             e: [
-                rand::random::<f64>(),
-                rand::random::<f64>(),
-                rand::random::<f64>(),
+                rng.gen_range(0.0..1.0),
+                rng.gen_range(0.0..1.0),
+                rng.gen_range(0.0..1.0),
             ],
         }
     }
@@ -258,7 +260,7 @@ pub fn unit_vector(v: &Vec3) -> Vec3 {
 
 // Generate a vec on xy plane
 pub fn random_in_unit_disk() -> Vec3 {
-    let mut rng = rand::thread_rng();
+    let mut rng = rng();
     loop {
         let p = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
         if p.length_squared() < 1.0 {
@@ -291,7 +293,7 @@ pub fn _random_on_hemisphere(normal: &Vec3) -> Vec3 {
 
 pub fn random_cosine_direction() -> Vec3 {
     // sphere coordination
-    let mut rng = rand::thread_rng();
+    let mut rng = rng();
     let r1 = rng.gen_range(0.0..1.0);
     let r2: f64 = rng.gen_range(0.0..1.0);
 
@@ -1,4 +1,51 @@
+pub mod animation;
 pub mod bouncing_spheres;
 pub mod cornell_box;
+pub mod crowd;
 pub mod final_scene;
 pub mod obj_test;
+pub mod scene_file;
+
+use crate::camera::background::BackGround;
+use crate::camera::Camera;
+use crate::hittable::hittable_list::HittableList;
+use crate::util::RenderParam;
+use crate::worlds::animation::AnimationBuilder;
+
+// (world, lights, camera, background), what the renderer consumes
+pub type Scene = (HittableList, HittableList, Camera, BackGround);
+pub type SceneBuilder = fn(&RenderParam) -> Scene;
+
+// every scene selectable by name from the command line
+pub const SCENES: [(&str, SceneBuilder); 7] = [
+    ("final_scene", final_scene::final_scene),
+    ("_cornell_box_normal", cornell_box::_cornell_box_normal),
+    ("_cornell_box_nmap", cornell_box::_cornell_box_nmap),
+    ("_cornell_box_motion", cornell_box::_cornell_box_motion),
+    ("_bouncing_spheres", bouncing_spheres::_bouncing_spheres),
+    ("_obj_test", obj_test::_obj_test),
+    ("_crowd", crowd::_crowd),
+];
+
+// scenes that --frames can render as a sequence
+pub const ANIMATIONS: [(&str, AnimationBuilder); 2] = [
+    ("_cornell_box_orbit", cornell_box::_cornell_box_orbit),
+    ("_cornell_box_dance", cornell_box::_cornell_box_dance),
+];
+
+// the leading underscore is optional, "cornell_box_normal" works too
+pub fn scene_by_name(name: &str) -> Option<SceneBuilder> {
+    let name = name.trim_start_matches('_');
+    SCENES
+        .iter()
+        .find(|(scene_name, _)| scene_name.trim_start_matches('_') == name)
+        .map(|(_, builder)| *builder)
+}
+
+pub fn animation_by_name(name: &str) -> Option<AnimationBuilder> {
+    let name = name.trim_start_matches('_');
+    ANIMATIONS
+        .iter()
+        .find(|(anim_name, _)| anim_name.trim_start_matches('_') == name)
+        .map(|(_, builder)| *builder)
+}
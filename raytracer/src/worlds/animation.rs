@@ -0,0 +1,73 @@
+// scenes that change from frame to frame, rendered with --frames. the
+// camera is keyed here; objects and lights are keyed by the scene builder,
+// which gets the frame and reads its own tracks
+
+use crate::camera::Camera;
+use crate::util::vec3::Vec3;
+use crate::util::RenderParam;
+use crate::worlds::Scene;
+use std::ops::{Add, Mul};
+
+// values at some frames, linear in between and held before the first key
+// and after the last
+pub struct Track<T> {
+    keys: Vec<(f64, T)>,
+}
+
+impl<T: Copy + Add<Output = T> + Mul<f64, Output = T>> Track<T> {
+    // panics without keys
+    pub fn new(mut keys: Vec<(f64, T)>) -> Self {
+        assert!(!keys.is_empty(), "track without keys");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Track { keys }
+    }
+
+    pub fn at(&self, frame: f64) -> T {
+        let i = self.keys.partition_point(|k| k.0 <= frame);
+        if i == 0 {
+            self.keys[0].1
+        } else if i == self.keys.len() {
+            self.keys[i - 1].1
+        } else {
+            let ((f0, a), (f1, b)) = (self.keys[i - 1], self.keys[i]);
+            let f = (frame - f0) / (f1 - f0);
+            a * (1.0 - f) + b * f
+        }
+    }
+}
+
+// whatever is None stays as the scene builder set it
+#[derive(Default)]
+pub struct CameraKeys {
+    pub lookfrom: Option<Track<Vec3>>,
+    pub lookat: Option<Track<Vec3>>,
+    pub vfov: Option<Track<f64>>,
+}
+
+impl CameraKeys {
+    pub fn apply(&self, cam: &mut Camera, frame: f64) {
+        if let Some(track) = &self.lookfrom {
+            cam.lookfrom = track.at(frame);
+        }
+        if let Some(track) = &self.lookat {
+            cam.lookat = track.at(frame);
+        }
+        if let Some(track) = &self.vfov {
+            cam.vfov = track.at(frame);
+        }
+        cam.initialize();
+    }
+}
+
+pub struct Animation {
+    // what --frames renders when it is not given a range, both included
+    pub frames: (u32, u32),
+    // the scene at a frame, before the camera keys are applied
+    pub build: fn(&RenderParam, f64) -> Scene,
+    // nothing but the camera moves: the scene and its bvhs are built once
+    // and kept for every frame
+    pub camera_only: bool,
+    pub camera: CameraKeys,
+}
+
+pub type AnimationBuilder = fn() -> Animation;
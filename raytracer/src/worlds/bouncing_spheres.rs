@@ -1,6 +1,6 @@
 use crate::camera::background::BackGround;
 use crate::camera::Camera;
-use crate::hittable::bvh::BvhNode;
+use crate::hittable::bvh::build_bvh;
 use crate::hittable::hittable_list::HittableList;
 use crate::hittable::instances::sphere::Sphere;
 use crate::materials::dielectric::Dielectric;
@@ -8,12 +8,13 @@ use crate::materials::lambertian::Lambertian;
 use crate::materials::metal::Metal;
 use crate::textures::SolidColor;
 use crate::util::color::Color;
+use crate::util::random::rng;
 use crate::util::vec3::{Point3, Vec3};
-use crate::util::{ASPECT_RATIO, IMAGE_WIDTH, MAX_RECURSE_DEPTH, SAMPLES_PER_PIXEL};
+use crate::util::RenderParam;
 use rand::Rng;
 use std::sync::Arc;
 
-pub fn _bouncing_spheres() -> (HittableList, HittableList, Camera, BackGround) {
+pub fn _bouncing_spheres(param: &RenderParam) -> (HittableList, HittableList, Camera, BackGround) {
     // world
     let mut world = HittableList::default();
     // materials
@@ -25,7 +26,7 @@ pub fn _bouncing_spheres() -> (HittableList, HittableList, Camera, BackGround) {
     )));
 
     // rand balls
-    let mut rng = rand::thread_rng();
+    let mut rng = rng();
     for a in -11..11 {
         for b in -11..11 {
             let choose_mat = rng.gen_range(0.0..1.0);
@@ -47,7 +48,7 @@ pub fn _bouncing_spheres() -> (HittableList, HittableList, Camera, BackGround) {
                 } else if choose_mat < 0.9 {
                     // metal
                     let albedo = Color::random_in(0.5, 1.0);
-                    let fuzz = rand::random::<f64>();
+                    let fuzz = rng.gen_range(0.0..1.0);
                     world.add(Box::new(Sphere::new_static(
                         center,
                         0.2,
@@ -85,17 +86,21 @@ pub fn _bouncing_spheres() -> (HittableList, HittableList, Camera, BackGround) {
     )));
 
     let mut world_ = HittableList::default();
-    world_.add(Box::new(BvhNode::new_list(world)));
+    world_.add(build_bvh(
+        world.objects,
+        param.bvh.unwrap_or_default(),
+        param.bvh_layout,
+    ));
     let world = world_;
 
     let lights = HittableList::default();
 
     let mut cam = Camera::default();
 
-    cam.aspect_ratio = ASPECT_RATIO;
-    cam.image_width = IMAGE_WIDTH;
-    cam.samples_per_pixel = SAMPLES_PER_PIXEL;
-    cam.max_recurse_depth = MAX_RECURSE_DEPTH;
+    cam.aspect_ratio = param.aspect_ratio;
+    cam.image_width = param.image_width;
+    cam.samples_per_pixel = param.samples_per_pixel;
+    cam.max_recurse_depth = param.max_recurse_depth;
 
     cam.vfov = 20.0;
     cam.lookfrom = Point3::new(13.0, 2.0, 3.0);
@@ -1,81 +1,44 @@
 use crate::camera::background::BackGround;
 use crate::camera::Camera;
-use crate::features::normal_map::{MapMap, OriginMap};
+use crate::features::normal_map::{MapMap, NormalMap, OriginMap};
 use crate::hittable::hittable_list::HittableList;
 use crate::hittable::instances::flats::{cube, Quad};
 use crate::hittable::instances::sphere::Sphere;
+use crate::hittable::transforms::motion::{pose_matrix, Keyframe, Motion};
 use crate::hittable::transforms::rotate_y::RotateY;
+use crate::hittable::transforms::transform::Transform;
 use crate::hittable::transforms::translate::Translate;
 use crate::materials::dielectric::Dielectric;
 use crate::materials::diffuse_light::DiffuseLight;
 use crate::materials::lambertian::Lambertian;
 use crate::materials::metal::Metal;
+use crate::materials::Material;
 use crate::textures::image_texture::ImageTexture;
 use crate::textures::SolidColor;
 use crate::util::color::Color;
 use crate::util::vec3::{Point3, Vec3};
-use crate::util::{ASPECT_RATIO, IMAGE_WIDTH, MAX_RECURSE_DEPTH, SAMPLES_PER_PIXEL};
+use crate::util::{degrees_to_radians, RenderParam};
+use crate::worlds::animation::{Animation, CameraKeys, Track};
 use std::sync::Arc;
 
-pub fn _cornell_box_normal() -> (HittableList, HittableList, Camera, BackGround) {
+pub fn _cornell_box_normal(
+    param: &RenderParam,
+) -> (HittableList, HittableList, Camera, BackGround) {
     let mut world = HittableList::default();
     let origin_nmap = OriginMap::default();
 
-    let red = Lambertian::<SolidColor>::new_color(Color::new(0.65, 0.05, 0.05));
     let white = Lambertian::<SolidColor>::new_color(Color::new(0.73, 0.73, 0.73));
-    let green = Lambertian::<SolidColor>::new_color(Color::new(0.12, 0.45, 0.15));
     let blue = Lambertian::<SolidColor>::new_color(Color::new(0.4, 0.6, 0.8));
     let pink = Lambertian::<SolidColor>::new_color(Color::new(0.8, 0.4, 0.4));
     let light = DiffuseLight::<SolidColor>::new_color(Color::new(6.0, 6.0, 6.0));
 
-    world.add(Box::new(Quad::new(
-        Point3::new(555.0, 0.0, 0.0),
-        Vec3::new(0.0, 555.0, 0.0),
-        Vec3::new(0.0, 0.0, 555.0),
-        green,
-        origin_nmap.clone(),
-    )));
-    world.add(Box::new(Quad::new(
-        Point3::default(),
-        Vec3::new(0.0, 555.0, 0.0),
-        Vec3::new(0.0, 0.0, 555.0),
-        red,
-        origin_nmap.clone(),
-    )));
-    world.add(Box::new(Quad::new(
-        Point3::new(127.5, 554.0, 127.5),
-        Vec3::new(300.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, 300.0),
-        light,
-        origin_nmap.clone(),
-    )));
-    world.add(Box::new(Quad::new(
-        Point3::default(),
-        Vec3::new(555.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, 555.0),
-        white.clone(),
-        origin_nmap.clone(),
-    )));
-    world.add(Box::new(Quad::new(
-        Point3::new(555.0, 555.0, 555.0),
-        Vec3::new(-555.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, -555.0),
-        white.clone(),
-        origin_nmap.clone(),
-    )));
-    world.add(Box::new(Quad::new(
-        Point3::new(0.0, 0.0, 555.0),
-        Vec3::new(555.0, 0.0, 0.0),
-        Vec3::new(0.0, 555.0, 0.0),
-        white,
-        origin_nmap.clone(),
-    )));
+    walls(&mut world, light, white, origin_nmap);
 
     let box1 = cube(
         Point3::new(0.0, 0.0, 0.0),
         Point3::new(165.0, 330.0, 165.0),
         blue,
-        origin_nmap.clone(),
+        origin_nmap,
     ); // material=aluminum
     let box1 = RotateY::new(box1, 15.0);
     let box1 = Box::new(Translate::new(box1, Vec3::new(265.0, 0.0, 295.0)));
@@ -85,7 +48,7 @@ pub fn _cornell_box_normal() -> (HittableList, HittableList, Camera, BackGround)
         Point3::new(0.0, 0.0, 0.0),
         Point3::new(165.0, 165.0, 165.0),
         pink,
-        origin_nmap.clone(),
+        origin_nmap,
     );
     let box2 = RotateY::new(box2, -18.0);
     let box2 = Box::new(Translate::new(box2, Vec3::new(130.0, 300.0, 65.0)));
@@ -100,48 +63,22 @@ pub fn _cornell_box_normal() -> (HittableList, HittableList, Camera, BackGround)
 
     let mut lights = HittableList::default();
     let m = Lambertian::<SolidColor>::new_color(Color::default());
-    lights.add(Box::new(Quad::new(
-        Point3::new(127.5, 554.0, 127.5),
-        Vec3::new(300.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, 300.0),
-        m.clone(),
-        origin_nmap,
-    )));
+    lights.add(Box::new(ceiling_light(m.clone())));
     lights.add(Box::new(Sphere::new_static(
         Point3::new(190.0, 90.0, 190.0),
         90.0,
         m,
     )));
 
-    let mut cam = Camera::default();
-
-    cam.aspect_ratio = ASPECT_RATIO;
-    cam.image_width = IMAGE_WIDTH;
-    cam.samples_per_pixel = SAMPLES_PER_PIXEL;
-    cam.max_recurse_depth = MAX_RECURSE_DEPTH;
-
-    cam.vfov = 40.0;
-    cam.lookfrom = Point3::new(278.0, 278.0, -800.0);
-    cam.lookat = Point3::new(278.0, 278.0, 0.0);
-    cam.vup = Vec3::new(0.0, 1.0, 0.0);
-
-    cam.defocus_angle = 0.0;
-    cam.focus_dist = 10.0;
-
-    cam.initialize();
+    let cam = camera(param);
 
     let back_ground = BackGround::new(Arc::new(SolidColor::new_color(Color::new(0.5, 0.7, 1.0))));
 
     (world, lights, cam, back_ground)
 }
 
-pub fn _cornell_box_nmap() -> (HittableList, HittableList, Camera, BackGround) {
+pub fn _cornell_box_nmap(param: &RenderParam) -> (HittableList, HittableList, Camera, BackGround) {
     let mut world = HittableList::default();
-    let origin_nmap = OriginMap::default();
-
-    let red = Lambertian::<SolidColor>::new_color(Color::new(0.65, 0.05, 0.05));
-    let white = Lambertian::<SolidColor>::new_color(Color::new(0.73, 0.73, 0.73));
-    let green = Lambertian::<SolidColor>::new_color(Color::new(0.12, 0.45, 0.15));
     let light = DiffuseLight::<SolidColor>::new_color(Color::new(6.0, 6.0, 6.0));
 
     let apple = Lambertian::new_tex(ImageTexture::new_path("source/normalmaps/sourapple.jpg"));
@@ -151,48 +88,7 @@ pub fn _cornell_box_nmap() -> (HittableList, HittableList, Camera, BackGround) {
     let clouds = Metal::new_tex(ImageTexture::new_path("source/normalmaps/clouds.jpg"), 0.2);
     let clouds_nmap = MapMap::new("source/normalmaps/clouds.png");
 
-    world.add(Box::new(Quad::new(
-        Point3::new(555.0, 0.0, 0.0),
-        Vec3::new(0.0, 555.0, 0.0),
-        Vec3::new(0.0, 0.0, 555.0),
-        green,
-        origin_nmap.clone(),
-    )));
-    world.add(Box::new(Quad::new(
-        Point3::default(),
-        Vec3::new(0.0, 555.0, 0.0),
-        Vec3::new(0.0, 0.0, 555.0),
-        red,
-        origin_nmap.clone(),
-    )));
-    world.add(Box::new(Quad::new(
-        Point3::new(127.5, 554.0, 127.5),
-        Vec3::new(300.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, 300.0),
-        light,
-        origin_nmap.clone(),
-    )));
-    world.add(Box::new(Quad::new(
-        Point3::default(),
-        Vec3::new(555.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, 555.0),
-        white.clone(),
-        origin_nmap.clone(),
-    )));
-    world.add(Box::new(Quad::new(
-        Point3::new(555.0, 555.0, 555.0),
-        Vec3::new(-555.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, -555.0),
-        white,
-        origin_nmap.clone(),
-    )));
-    world.add(Box::new(Quad::new(
-        Point3::new(0.0, 0.0, 555.0),
-        Vec3::new(555.0, 0.0, 0.0),
-        Vec3::new(0.0, 555.0, 0.0),
-        apple,
-        apple_nmap,
-    )));
+    walls(&mut world, light, apple, apple_nmap);
 
     let box1 = cube(
         Point3::new(0.0, 0.0, 0.0),
@@ -224,25 +120,260 @@ pub fn _cornell_box_nmap() -> (HittableList, HittableList, Camera, BackGround) {
 
     let mut lights = HittableList::default();
     let m = Lambertian::<SolidColor>::new_color(Color::default());
-    lights.add(Box::new(Quad::new(
-        Point3::new(127.5, 554.0, 127.5),
-        Vec3::new(300.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, 300.0),
-        m.clone(),
+    lights.add(Box::new(ceiling_light(m.clone())));
+    lights.add(Box::new(Sphere::new_static(
+        Point3::new(190.0, 90.0, 190.0),
+        90.0,
+        m,
+    )));
+
+    let cam = camera(param);
+
+    let back_ground = BackGround::new(Arc::new(SolidColor::new_color(Color::new(0.2, 0.1, 0.2))));
+
+    (world, lights, cam, back_ground)
+}
+
+// keyframed motion blur: a box slides and spins, a ball grows and a small
+// light flies across the box while the shutter is open
+pub fn _cornell_box_motion(
+    param: &RenderParam,
+) -> (HittableList, HittableList, Camera, BackGround) {
+    let mut world = HittableList::default();
+    let origin_nmap = OriginMap::default();
+
+    let white = Lambertian::<SolidColor>::new_color(Color::new(0.73, 0.73, 0.73));
+    let blue = Lambertian::<SolidColor>::new_color(Color::new(0.4, 0.6, 0.8));
+    let light = DiffuseLight::<SolidColor>::new_color(Color::new(6.0, 6.0, 6.0));
+
+    walls(&mut world, light, white, origin_nmap);
+
+    // centered on its foot so it spins in place
+    let box1 = cube(
+        Point3::new(-82.5, 0.0, -82.5),
+        Point3::new(82.5, 330.0, 82.5),
+        blue,
         origin_nmap,
+    );
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let box1 = Motion::new(
+        box1,
+        vec![
+            Keyframe::new(0.0).translate(Vec3::new(300.0, 0.0, 380.0)),
+            Keyframe::new(0.5)
+                .rotate(up, 45.0)
+                .translate(Vec3::new(350.0, 0.0, 380.0)),
+            Keyframe::new(1.0)
+                .rotate(up, 90.0)
+                .translate(Vec3::new(400.0, 0.0, 380.0)),
+        ],
+    );
+    world.add(Box::new(box1));
+
+    let glass = Dielectric::new(1.5);
+    let grow = vec![
+        Keyframe::new(0.0)
+            .scale(Vec3::new(0.6, 0.6, 0.6))
+            .translate(Vec3::new(160.0, 54.0, 190.0)),
+        Keyframe::new(1.0).translate(Vec3::new(160.0, 90.0, 190.0)),
+    ];
+    world.add(Box::new(Motion::new(
+        Sphere::new_static(Point3::default(), 90.0, glass),
+        grow.clone(),
+    )));
+
+    let fly = vec![
+        Keyframe::new(0.0).translate(Vec3::new(120.0, 420.0, 200.0)),
+        Keyframe::new(1.0).translate(Vec3::new(440.0, 420.0, 200.0)),
+    ];
+    let spark = DiffuseLight::<SolidColor>::new_color(Color::new(20.0, 14.0, 6.0));
+    world.add(Box::new(Motion::new(
+        Sphere::new_static(Point3::default(), 15.0, spark),
+        fly.clone(),
+    )));
+
+    let mut lights = HittableList::default();
+    let m = Lambertian::<SolidColor>::new_color(Color::default());
+    lights.add(Box::new(ceiling_light(m.clone())));
+    lights.add(Box::new(Motion::new(
+        Sphere::new_static(Point3::default(), 90.0, m.clone()),
+        grow,
     )));
+    lights.add(Box::new(Motion::new(
+        Sphere::new_static(Point3::default(), 15.0, m),
+        fly,
+    )));
+
+    let mut cam = camera(param);
+    cam.shutter_open = 0.0;
+    cam.shutter_close = 1.0;
+
+    let back_ground = BackGround::new(Arc::new(SolidColor::new_color(Color::new(0.5, 0.7, 1.0))));
+
+    (world, lights, cam, back_ground)
+}
+
+// the camera swings around the box and zooms in; nothing else moves, so
+// the box is built once
+pub fn _cornell_box_orbit() -> Animation {
+    let center = Point3::new(278.0, 278.0, 278.0);
+    let lookfrom = (0..=4)
+        .map(|k| {
+            let theta = degrees_to_radians(-30.0 + 15.0 * k as f64);
+            let at = center + Vec3::new(1078.0 * theta.sin(), 0.0, -1078.0 * theta.cos());
+            (1.0 + 47.0 * k as f64 / 4.0, at)
+        })
+        .collect();
+    Animation {
+        frames: (1, 48),
+        build: |param, _| _cornell_box_normal(param),
+        camera_only: true,
+        camera: CameraKeys {
+            lookfrom: Some(Track::new(lookfrom)),
+            lookat: Some(Track::new(vec![(1.0, center)])),
+            vfov: Some(Track::new(vec![(1.0, 40.0), (48.0, 30.0)])),
+        },
+    }
+}
+
+// the tall box turns and hops while the light dims and comes back, so the
+// world is built again for every frame
+pub fn _cornell_box_dance() -> Animation {
+    Animation {
+        frames: (1, 48),
+        build: cornell_box_dance_frame,
+        camera_only: false,
+        camera: CameraKeys::default(),
+    }
+}
+
+fn cornell_box_dance_frame(
+    param: &RenderParam,
+    frame: f64,
+) -> (HittableList, HittableList, Camera, BackGround) {
+    let mut world = HittableList::default();
+    let origin_nmap = OriginMap::default();
+
+    let power = Track::new(vec![(1.0, 8.0), (24.0, 1.5), (48.0, 8.0)]);
+    let white = Lambertian::<SolidColor>::new_color(Color::new(0.73, 0.73, 0.73));
+    let blue = Lambertian::<SolidColor>::new_color(Color::new(0.4, 0.6, 0.8));
+    let light = DiffuseLight::<SolidColor>::new_color(Color::new(1.0, 1.0, 1.0) * power.at(frame));
+
+    walls(&mut world, light, white, origin_nmap);
+
+    let box1 = cube(
+        Point3::new(-82.5, 0.0, -82.5),
+        Point3::new(82.5, 330.0, 82.5),
+        blue,
+        origin_nmap,
+    );
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let place = Vec3::new(347.5, 0.0, 377.5);
+    let keys = [
+        Keyframe::new(1.0).rotate(up, 15.0).translate(place),
+        Keyframe::new(12.0)
+            .rotate(up, 60.0)
+            .translate(place + Vec3::new(0.0, 80.0, 0.0)),
+        Keyframe::new(24.0).rotate(up, 105.0).translate(place),
+        Keyframe::new(36.0)
+            .rotate(up, 150.0)
+            .translate(place + Vec3::new(0.0, 80.0, 0.0)),
+        Keyframe::new(48.0).rotate(up, 195.0).translate(place),
+    ];
+    world.add(Box::new(Transform::new(box1, pose_matrix(&keys, frame))));
+
+    let glass = Dielectric::new(1.5);
+    world.add(Box::new(Sphere::new_static(
+        Point3::new(190.0, 90.0, 190.0),
+        90.0,
+        glass,
+    )));
+
+    let mut lights = HittableList::default();
+    let m = Lambertian::<SolidColor>::new_color(Color::default());
+    lights.add(Box::new(ceiling_light(m.clone())));
     lights.add(Box::new(Sphere::new_static(
         Point3::new(190.0, 90.0, 190.0),
         90.0,
         m,
     )));
 
+    let cam = camera(param);
+
+    let back_ground = BackGround::new(Arc::new(SolidColor::new_color(Color::new(0.5, 0.7, 1.0))));
+
+    (world, lights, cam, back_ground)
+}
+
+// the red and green sides, the white floor and ceiling, the ceiling light
+// and the back wall, which the builders above dress differently
+fn walls<M: Material + 'static, N: NormalMap + 'static>(
+    world: &mut HittableList,
+    light: DiffuseLight<SolidColor>,
+    back: M,
+    back_nmap: N,
+) {
+    let origin_nmap = OriginMap::default();
+    let red = Lambertian::<SolidColor>::new_color(Color::new(0.65, 0.05, 0.05));
+    let white = Lambertian::<SolidColor>::new_color(Color::new(0.73, 0.73, 0.73));
+    let green = Lambertian::<SolidColor>::new_color(Color::new(0.12, 0.45, 0.15));
+
+    world.add(Box::new(Quad::new(
+        Point3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        green,
+        origin_nmap,
+    )));
+    world.add(Box::new(Quad::new(
+        Point3::default(),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        red,
+        origin_nmap,
+    )));
+    world.add(Box::new(ceiling_light(light)));
+    world.add(Box::new(Quad::new(
+        Point3::default(),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        white.clone(),
+        origin_nmap,
+    )));
+    world.add(Box::new(Quad::new(
+        Point3::new(555.0, 555.0, 555.0),
+        Vec3::new(-555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -555.0),
+        white,
+        origin_nmap,
+    )));
+    world.add(Box::new(Quad::new(
+        Point3::new(0.0, 0.0, 555.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        back,
+        back_nmap,
+    )));
+}
+
+// in the world it glows, in the lights list it only tells where to sample
+fn ceiling_light<M: Material>(mat: M) -> Quad<M, OriginMap> {
+    Quad::new(
+        Point3::new(127.5, 554.0, 127.5),
+        Vec3::new(300.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 300.0),
+        mat,
+        OriginMap::default(),
+    )
+}
+
+fn camera(param: &RenderParam) -> Camera {
     let mut cam = Camera::default();
 
-    cam.aspect_ratio = ASPECT_RATIO;
-    cam.image_width = IMAGE_WIDTH;
-    cam.samples_per_pixel = SAMPLES_PER_PIXEL;
-    cam.max_recurse_depth = MAX_RECURSE_DEPTH;
+    cam.aspect_ratio = param.aspect_ratio;
+    cam.image_width = param.image_width;
+    cam.samples_per_pixel = param.samples_per_pixel;
+    cam.max_recurse_depth = param.max_recurse_depth;
 
     cam.vfov = 40.0;
     cam.lookfrom = Point3::new(278.0, 278.0, -800.0);
@@ -253,8 +384,5 @@ pub fn _cornell_box_nmap() -> (HittableList, HittableList, Camera, BackGround) {
     cam.focus_dist = 10.0;
 
     cam.initialize();
-
-    let back_ground = BackGround::new(Arc::new(SolidColor::new_color(Color::new(0.2, 0.1, 0.2))));
-
-    (world, lights, cam, back_ground)
+    cam
 }
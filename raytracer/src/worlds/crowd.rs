@@ -0,0 +1,133 @@
+use crate::camera::background::BackGround;
+use crate::camera::Camera;
+use crate::features::normal_map::OriginMap;
+use crate::features::obj_mesh::{obj_mesh, LoadParam};
+use crate::hittable::bvh::{build_bvh, BvhStrategy};
+use crate::hittable::hittable_list::HittableList;
+use crate::hittable::instances::flats::Quad;
+use crate::hittable::instances::sphere::Sphere;
+use crate::hittable::transforms::instance::Instance;
+use crate::hittable::Hittable;
+use crate::materials::diffuse_light::DiffuseLight;
+use crate::materials::lambertian::Lambertian;
+use crate::materials::metal::Metal;
+use crate::materials::Material;
+use crate::textures::SolidColor;
+use crate::util::color::Color;
+use crate::util::mat4::Mat4;
+use crate::util::random::rng;
+use crate::util::vec3::{Point3, Vec3};
+use crate::util::RenderParam;
+use crate::worlds::final_scene::rand_color_gen;
+use rand::Rng;
+use std::sync::Arc;
+
+// one patrick loaded once and placed a few hundred times, some of them
+// repainted, to show off instancing
+pub fn _crowd(param: &RenderParam) -> (HittableList, HittableList, Camera, BackGround) {
+    let mut world = HittableList::default();
+    let mut lights = HittableList::default();
+    let origin_nmap = OriginMap::default();
+    let bvh = param.bvh.unwrap_or(BvhStrategy::Sah);
+
+    let ground = Lambertian::<SolidColor>::new_color(Color::new(0.5, 0.55, 0.45));
+    world.add(Box::new(Quad::new(
+        Point3::new(-200.0, 0.0, -200.0),
+        Vec3::new(0.0, 0.0, 400.0),
+        Vec3::new(400.0, 0.0, 0.0),
+        ground,
+        origin_nmap,
+    )));
+
+    let patrick_load_param = LoadParam {
+        file: "source/objs/patrick.obj",
+        zoom: 2.5,
+        offset: Vec3::default(),
+        rot_x: 0.0,
+        rot_y: 0.0,
+        rot_z: 0.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
+    };
+    let patrick: Arc<dyn Hittable> = obj_mesh(patrick_load_param)
+        .unwrap_or_else(|err| panic!("{}", err))
+        .into();
+    // stand it on the ground, centered on the y axis
+    let bbox = patrick.bounding_box();
+    let feet = Vec3::new(
+        -(bbox.x.min + bbox.x.max) / 2.0,
+        -bbox.y.min,
+        -(bbox.z.min + bbox.z.max) / 2.0,
+    );
+
+    let mut rng = rng();
+    let mut crowd = HittableList::default();
+    for i in -12..=12 {
+        for j in -12..=12 {
+            let place = Vec3::new(
+                i as f64 * 3.0 + rng.gen_range(-0.8..0.8),
+                0.0,
+                j as f64 * 3.0 + rng.gen_range(-0.8..0.8),
+            );
+            let size = rng.gen_range(0.7..1.3);
+            let instance = Instance::new(patrick.clone(), Mat4::translation(feet))
+                .scale(Vec3::new(size, size, size))
+                .rotate(Vec3::new(0.0, 1.0, 0.0), rng.gen_range(0.0..360.0))
+                .translate(place);
+            // a third keep their own texture
+            let instance = match rng.gen_range(0..3) {
+                0 => instance,
+                1 => {
+                    let color = rand_color_gen(0.1, 0.9, 0.1, 0.9, 0.1, 0.9);
+                    let material: Arc<dyn Material> =
+                        Arc::new(Lambertian::<SolidColor>::new_color(color));
+                    instance.with_material(material)
+                }
+                _ => {
+                    let color = rand_color_gen(0.5, 1.0, 0.5, 1.0, 0.5, 1.0);
+                    let material: Arc<dyn Material> = Arc::new(Metal::<SolidColor>::new_color(
+                        color,
+                        rng.gen_range(0.0..0.3),
+                    ));
+                    instance.with_material(material)
+                }
+            };
+            crowd.add(Box::new(instance));
+        }
+    }
+    world.add(build_bvh(crowd.objects, bvh, param.bvh_layout));
+
+    let sun = Sphere::new_static(
+        Point3::new(-60.0, 120.0, 80.0),
+        20.0,
+        DiffuseLight::<SolidColor>::new_color(Color::new(8.0, 7.5, 7.0)),
+    );
+    lights.add(Box::new(Sphere::new_static(
+        sun.center1,
+        sun.radius,
+        Lambertian::<SolidColor>::new_color(Color::default()),
+    )));
+    world.add(Box::new(sun));
+
+    let mut cam = Camera::default();
+
+    cam.aspect_ratio = param.aspect_ratio;
+    cam.image_width = param.image_width;
+    cam.samples_per_pixel = param.samples_per_pixel;
+    cam.max_recurse_depth = param.max_recurse_depth;
+
+    cam.vfov = 35.0;
+    cam.lookfrom = Point3::new(24.0, 12.0, 48.0);
+    cam.lookat = Point3::new(0.0, 1.0, 0.0);
+    cam.vup = Vec3::new(0.0, 1.0, 0.0);
+
+    cam.defocus_angle = 0.0;
+    cam.focus_dist = 10.0;
+
+    cam.initialize();
+
+    let back_ground = BackGround::new(Arc::new(SolidColor::new_color(Color::new(0.5, 0.7, 1.0))));
+
+    (world, lights, cam, back_ground)
+}
@@ -2,7 +2,7 @@ use crate::camera::background::BackGround;
 use crate::camera::Camera;
 use crate::features::normal_map::{MapMap, OriginMap};
 use crate::features::obj_mesh::{obj_mesh, LoadParam};
-use crate::hittable::bvh::BvhNode;
+use crate::hittable::bvh::{build_bvh, BvhStrategy};
 use crate::hittable::hittable_list::HittableList;
 use crate::hittable::instances::flats::{cube, Quad};
 use crate::hittable::instances::sphere::Sphere;
@@ -15,17 +15,21 @@ use crate::materials::metal::Metal;
 use crate::textures::image_texture::ImageTexture;
 use crate::textures::SolidColor;
 use crate::util::color::Color;
+use crate::util::random::rng;
+use crate::util::spectrum::Dispersion;
 use crate::util::vec3::{Point3, Vec3};
-use crate::util::{ASPECT_RATIO, IMAGE_WIDTH, MAX_RECURSE_DEPTH, SAMPLES_PER_PIXEL};
+use crate::util::RenderParam;
 use rand::Rng;
 use std::sync::Arc;
 
-pub fn final_scene() -> (HittableList, HittableList, Camera, BackGround) {
+pub fn final_scene(param: &RenderParam) -> (HittableList, HittableList, Camera, BackGround) {
     let mut world = HittableList::default();
     let mut lights = HittableList::default();
 
     let m = Lambertian::<SolidColor>::new_color(Color::default());
     let origin_nmap = OriginMap::default();
+    // the kirbies are large meshes, worth the slower build
+    let bvh = param.bvh.unwrap_or(BvhStrategy::Sah);
 
     let ground_mat = Lambertian::<SolidColor>::new_color(Color::new(1.0, 1.0, 1.0));
     world.add(Box::new(Sphere::new_static(
@@ -114,116 +118,146 @@ pub fn final_scene() -> (HittableList, HittableList, Camera, BackGround) {
     }
 
     let fighter_kirby_load_param = LoadParam {
-        obj_file: "source/objs/kirbies/Fighter_Kirby/fighterkirby.obj",
+        file: "source/objs/kirbies/Fighter_Kirby/fighterkirby.obj",
         zoom: 1.0,
         offset: Vec3::new(-20.0, 0.0, 40.0),
         rot_x: 10.0,
         rot_y: 150.0,
         rot_z: -10.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let fighter_kirby = obj_mesh(fighter_kirby_load_param);
+    let fighter_kirby = obj_mesh(fighter_kirby_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(fighter_kirby);
 
     let sword_kirby_load_param = LoadParam {
-        obj_file: "source/objs/kirbies/Swordkirby/DolSwrodkirby.obj",
+        file: "source/objs/kirbies/Swordkirby/DolSwrodkirby.obj",
         zoom: 1.2,
         offset: Vec3::new(-5.0, 0.0, 20.0),
         rot_x: 15.0,
         rot_y: 120.0,
         rot_z: 10.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let sword_kirby = obj_mesh(sword_kirby_load_param);
+    let sword_kirby = obj_mesh(sword_kirby_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(sword_kirby);
 
     let plasma_kirby_load_param = LoadParam {
-        obj_file: "source/objs/kirbies/Plasmakirby/DolPlasmakirby.obj",
+        file: "source/objs/kirbies/Plasmakirby/DolPlasmakirby.obj",
         zoom: 1.0,
         offset: Vec3::new(-45.0, 2.0, -5.0),
         rot_x: -45.0,
         rot_y: -75.0,
         rot_z: 0.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let plasma_kirby = obj_mesh(plasma_kirby_load_param);
+    let plasma_kirby = obj_mesh(plasma_kirby_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(plasma_kirby);
 
     let ice_kirby_load_param = LoadParam {
-        obj_file: "source/objs/kirbies/Icekirby/DolIcekirby.obj",
+        file: "source/objs/kirbies/Icekirby/DolIcekirby.obj",
         zoom: 1.0,
         offset: Vec3::new(-35.0, 0.0, -35.0),
         rot_x: 0.0,
         rot_y: -30.0,
         rot_z: 30.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let ice_kirby = obj_mesh(ice_kirby_load_param);
+    let ice_kirby = obj_mesh(ice_kirby_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(ice_kirby);
 
     let needle_kirby_load_param = LoadParam {
-        obj_file: "source/objs/kirbies/Needlekirby/DolNeedlekirby.obj",
+        file: "source/objs/kirbies/Needlekirby/DolNeedlekirby.obj",
         zoom: 1.0,
         offset: Vec3::new(-10.0, 0.0, -30.0),
         rot_x: 0.0,
         rot_y: -10.0,
         rot_z: 5.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let needle_kirby = obj_mesh(needle_kirby_load_param);
+    let needle_kirby = obj_mesh(needle_kirby_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(needle_kirby);
 
     let tornado_kirby_load_param = LoadParam {
-        obj_file: "source/objs/kirbies/Tornadokirby/DolTornadokirby.obj",
+        file: "source/objs/kirbies/Tornadokirby/DolTornadokirby.obj",
         zoom: 1.0,
         offset: Vec3::new(-50.0, 18.0, 10.0),
         rot_x: 0.0,
         rot_y: -20.0,
         rot_z: -10.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let tornado_kirby = obj_mesh(tornado_kirby_load_param);
+    let tornado_kirby = obj_mesh(tornado_kirby_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(tornado_kirby);
 
     let wing_kirby_load_param = LoadParam {
-        obj_file: "source/objs/kirbies/Wingkirby/DolWingkirby.obj",
+        file: "source/objs/kirbies/Wingkirby/DolWingkirby.obj",
         zoom: 1.0,
         offset: Vec3::new(10.0, 12.0, 10.0),
         rot_x: 0.0,
         rot_y: 10.0,
         rot_z: -10.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let wing_kirby = obj_mesh(wing_kirby_load_param);
+    let wing_kirby = obj_mesh(wing_kirby_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(wing_kirby);
 
     let sleep_kirby_load_param = LoadParam {
-        obj_file: "source/objs/kirbies/Sleepkirby/DolSleepkirby.obj",
+        file: "source/objs/kirbies/Sleepkirby/DolSleepkirby.obj",
         zoom: 1.0,
         offset: Vec3::new(52.0, 20.0, -14.0),
         rot_x: 0.0,
         rot_y: 0.0,
         rot_z: 0.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let sleep_kirby = obj_mesh(sleep_kirby_load_param);
+    let sleep_kirby = obj_mesh(sleep_kirby_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(sleep_kirby);
 
     let allstar_kirby_load_param = LoadParam {
-        obj_file: "source/objs/kirbies/Allstarkirby/allstar.obj",
+        file: "source/objs/kirbies/Allstarkirby/allstar.obj",
         zoom: 1.5,
         offset: Vec3::new(-20.0, 20.0, 0.0),
         rot_x: 30.0,
         rot_y: -50.0,
         rot_z: -60.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let allstar_kirby = obj_mesh(allstar_kirby_load_param);
+    let allstar_kirby = obj_mesh(allstar_kirby_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(allstar_kirby);
 
     let beam_kirby_load_param = LoadParam {
-        obj_file: "source/objs/kirbies/Beamkirby/DolBeamkirby.obj",
+        file: "source/objs/kirbies/Beamkirby/DolBeamkirby.obj",
         zoom: 1.0,
         offset: Vec3::new(10.0, 0.0, -100.0),
         rot_x: 0.0,
         rot_y: -5.0,
         rot_z: 0.0,
+        bvh,
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let beam_kirby = obj_mesh(beam_kirby_load_param);
+    let beam_kirby = obj_mesh(beam_kirby_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(beam_kirby);
 
-    let mut rng = rand::thread_rng();
+    let mut rng = rng();
     let mut sky_world = HittableList::default();
 
     for i in -8..=8 {
@@ -306,7 +340,7 @@ pub fn final_scene() -> (HittableList, HittableList, Camera, BackGround) {
             }
         }
     }
-    world.add(Box::new(BvhNode::new_list(sky_world)));
+    world.add(build_bvh(sky_world.objects, bvh, param.bvh_layout));
 
     let clouds = Metal::new_tex(ImageTexture::new_path("source/normalmaps/clouds.jpg"), 0.2);
     let clouds_nmap = MapMap::new("source/normalmaps/clouds.png");
@@ -391,10 +425,10 @@ pub fn final_scene() -> (HittableList, HittableList, Camera, BackGround) {
 
     let mut cam = Camera::default();
 
-    cam.aspect_ratio = ASPECT_RATIO;
-    cam.image_width = IMAGE_WIDTH;
-    cam.samples_per_pixel = SAMPLES_PER_PIXEL;
-    cam.max_recurse_depth = MAX_RECURSE_DEPTH;
+    cam.aspect_ratio = param.aspect_ratio;
+    cam.image_width = param.image_width;
+    cam.samples_per_pixel = param.samples_per_pixel;
+    cam.max_recurse_depth = param.max_recurse_depth;
 
     cam.vfov = 20.0;
     cam.lookfrom = Point3::new(45.0, 60.0, 200.0);
@@ -413,7 +447,7 @@ pub fn final_scene() -> (HittableList, HittableList, Camera, BackGround) {
 }
 
 pub fn rand_color_gen(rl: f64, rr: f64, gl: f64, gr: f64, bl: f64, br: f64) -> Color {
-    let mut rng = rand::thread_rng();
+    let mut rng = rng();
     Color::new(
         rng.gen_range(rl..rr),
         rng.gen_range(gl..gr),
@@ -423,8 +457,9 @@ pub fn rand_color_gen(rl: f64, rr: f64, gl: f64, gr: f64, bl: f64, br: f64) -> C
 
 pub fn add_glass_outfit(world: &mut HittableList, sphere: &Sphere<DiffuseLight<SolidColor>>) {
     // 要不要中空呢？要的要的
-    let mat = Dielectric::new(1.5);
-    let in_mat = Dielectric::new(1.0 / 1.5);
+    // crown glass, which splits light into colors with --spectral
+    let mat = Dielectric::new(1.5).with_dispersion(Dispersion::BK7);
+    let in_mat = Dielectric::new(1.0 / 1.5).with_dispersion(Dispersion::BK7);
     let outfit = Sphere::new_static(sphere.center1, sphere.radius + 0.8, mat);
     let innerfit = Sphere::new_static(sphere.center1, sphere.radius + 0.3, in_mat);
     world.add(Box::new(outfit));
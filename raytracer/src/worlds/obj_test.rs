@@ -2,6 +2,7 @@ use crate::camera::background::BackGround;
 use crate::camera::Camera;
 use crate::features::normal_map::OriginMap;
 use crate::features::obj_mesh::{obj_mesh, LoadParam};
+use crate::hittable::bvh::BvhStrategy;
 use crate::hittable::hittable_list::HittableList;
 use crate::hittable::instances::flats::Quad;
 use crate::materials::diffuse_light::DiffuseLight;
@@ -9,10 +10,10 @@ use crate::materials::lambertian::Lambertian;
 use crate::textures::SolidColor;
 use crate::util::color::Color;
 use crate::util::vec3::{Point3, Vec3};
-use crate::util::{ASPECT_RATIO, IMAGE_WIDTH, MAX_RECURSE_DEPTH, SAMPLES_PER_PIXEL};
+use crate::util::RenderParam;
 use std::sync::Arc;
 
-pub fn _obj_test() -> (HittableList, HittableList, Camera, BackGround) {
+pub fn _obj_test(param: &RenderParam) -> (HittableList, HittableList, Camera, BackGround) {
     let mut world = HittableList::default();
     let origin_nmap = OriginMap::default();
 
@@ -26,53 +27,56 @@ pub fn _obj_test() -> (HittableList, HittableList, Camera, BackGround) {
         Vec3::new(0.0, 555.0, 0.0),
         Vec3::new(0.0, 0.0, 555.0),
         green,
-        origin_nmap.clone(),
+        origin_nmap,
     )));
     world.add(Box::new(Quad::new(
         Point3::default(),
         Vec3::new(0.0, 555.0, 0.0),
         Vec3::new(0.0, 0.0, 555.0),
         red,
-        origin_nmap.clone(),
+        origin_nmap,
     )));
     world.add(Box::new(Quad::new(
         Point3::new(127.5, 554.0, 127.5),
         Vec3::new(300.0, 0.0, 0.0),
         Vec3::new(0.0, 0.0, 300.0),
         light,
-        origin_nmap.clone(),
+        origin_nmap,
     )));
     world.add(Box::new(Quad::new(
         Point3::default(),
         Vec3::new(555.0, 0.0, 0.0),
         Vec3::new(0.0, 0.0, 555.0),
         white.clone(),
-        origin_nmap.clone(),
+        origin_nmap,
     )));
     world.add(Box::new(Quad::new(
         Point3::new(555.0, 555.0, 555.0),
         Vec3::new(-555.0, 0.0, 0.0),
         Vec3::new(0.0, 0.0, -555.0),
         white.clone(),
-        origin_nmap.clone(),
+        origin_nmap,
     )));
     world.add(Box::new(Quad::new(
         Point3::new(0.0, 0.0, 555.0),
         Vec3::new(555.0, 0.0, 0.0),
         Vec3::new(0.0, 555.0, 0.0),
         white,
-        origin_nmap.clone(),
+        origin_nmap,
     )));
 
     let patrick_load_param = LoadParam {
-        obj_file: "source/objs/patrick.obj",
+        file: "source/objs/patrick.obj",
         zoom: 150.0,
         offset: Vec3::new(320.0, 30.0, 277.5),
         rot_x: 0.0,
         rot_y: 165.0,
         rot_z: 0.0,
+        bvh: param.bvh.unwrap_or(BvhStrategy::Sah),
+        layout: param.bvh_layout,
+        flat_normals: false,
     };
-    let patrick = obj_mesh(patrick_load_param);
+    let patrick = obj_mesh(patrick_load_param).unwrap_or_else(|err| panic!("{}", err));
     world.add(patrick);
 
     let mut lights = HittableList::default();
@@ -87,10 +91,10 @@ pub fn _obj_test() -> (HittableList, HittableList, Camera, BackGround) {
 
     let mut cam = Camera::default();
 
-    cam.aspect_ratio = ASPECT_RATIO;
-    cam.image_width = IMAGE_WIDTH;
-    cam.samples_per_pixel = SAMPLES_PER_PIXEL;
-    cam.max_recurse_depth = MAX_RECURSE_DEPTH;
+    cam.aspect_ratio = param.aspect_ratio;
+    cam.image_width = param.image_width;
+    cam.samples_per_pixel = param.samples_per_pixel;
+    cam.max_recurse_depth = param.max_recurse_depth;
 
     cam.vfov = 40.0;
     cam.lookfrom = Point3::new(278.0, 278.0, -800.0);
@@ -0,0 +1,284 @@
+// load scenes from a TOML description instead of a Rust builder
+
+pub mod desc;
+pub mod export;
+pub mod loader;
+
+use crate::util::RenderParam;
+use crate::worlds::scene_file::desc::{default_background, SceneDesc};
+use crate::worlds::scene_file::loader::Loader;
+use crate::worlds::Scene;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use toml::Value;
+
+#[derive(Debug)]
+pub struct SceneError {
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub msg: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: {}", file, line, self.msg),
+            (Some(file), None) => write!(f, "{}: {}", file, self.msg),
+            (None, Some(line)) => write!(f, "line {}: {}", line, self.msg),
+            (None, None) => write!(f, "{}", self.msg),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+pub fn load_scene(path: &str, param: &RenderParam) -> Result<Scene, SceneError> {
+    let src = fs::read_to_string(path).map_err(|err| SceneError {
+        file: Some(String::from(path)),
+        line: None,
+        msg: format!("cannot read scene file: {}", err),
+    })?;
+    parse_scene(&src, param).map_err(|err| SceneError {
+        file: Some(String::from(path)),
+        ..err
+    })
+}
+
+pub fn parse_scene(src: &str, param: &RenderParam) -> Result<Scene, SceneError> {
+    let lines = SourceLines::new(src);
+    let desc = parse_desc(src, &lines)?;
+    Loader::new(lines).scene(&desc, param)
+}
+
+// Deserializing the whole file at once would report type errors inside
+// [[objects]] at the end of the array, so every entry is converted on its own.
+pub fn parse_desc(src: &str, lines: &SourceLines) -> Result<SceneDesc, SceneError> {
+    // syntax errors already carry "at line x column y"
+    let value: Value = toml::from_str(src).map_err(|err| SceneError {
+        file: None,
+        line: None,
+        msg: err.to_string(),
+    })?;
+    let Value::Table(mut table) = value else {
+        unreachable!("a TOML document is always a table");
+    };
+
+    let background = match table.remove("background") {
+        Some(v) => entry(lines, "background", v)?,
+        None => default_background(),
+    };
+    let camera = match table.remove("camera") {
+        Some(v) => entry(lines, "camera", v)?,
+        None => return Err(lines.error("camera", String::from("missing [camera] table"))),
+    };
+    let bvh = match table.remove("bvh") {
+        Some(v) => Some(entry(lines, "bvh", v)?),
+        None => None,
+    };
+    let textures = named_entries(lines, "textures", table.remove("textures"))?;
+    let materials = named_entries(lines, "materials", table.remove("materials"))?;
+    let objects = array_entries(lines, "objects", table.remove("objects"))?;
+    let lights = array_entries(lines, "lights", table.remove("lights"))?;
+
+    if let Some(key) = table.keys().next() {
+        return Err(lines.error(key, String::from("unknown key")));
+    }
+    Ok(SceneDesc {
+        background,
+        camera,
+        bvh,
+        textures,
+        materials,
+        objects,
+        lights,
+    })
+}
+
+fn entry<T: DeserializeOwned>(
+    lines: &SourceLines,
+    at: &str,
+    value: Value,
+) -> Result<T, SceneError> {
+    value
+        .try_into()
+        .map_err(|err| lines.error(at, err.to_string()))
+}
+
+fn named_entries<T: DeserializeOwned>(
+    lines: &SourceLines,
+    at: &str,
+    value: Option<Value>,
+) -> Result<BTreeMap<String, T>, SceneError> {
+    match value {
+        None => Ok(BTreeMap::new()),
+        Some(Value::Table(table)) => table
+            .into_iter()
+            .map(|(name, v)| Ok((name.clone(), entry(lines, &format!("{}.{}", at, name), v)?)))
+            .collect(),
+        Some(_) => Err(lines.error(at, String::from("expected a table"))),
+    }
+}
+
+fn array_entries<T: DeserializeOwned>(
+    lines: &SourceLines,
+    at: &str,
+    value: Option<Value>,
+) -> Result<Vec<T>, SceneError> {
+    match value {
+        None => Ok(Vec::new()),
+        Some(Value::Array(array)) => array
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| entry(lines, &format!("{}[{}]", at, i), v))
+            .collect(),
+        Some(_) => Err(lines.error(at, String::from("expected an array of tables"))),
+    }
+}
+
+// line numbers of the table headers, so that errors found after parsing
+// can still point at the entry they come from
+pub struct SourceLines {
+    headers: HashMap<String, usize>,
+}
+
+impl SourceLines {
+    pub fn new(src: &str) -> Self {
+        let mut headers = HashMap::new();
+        let mut counters = HashMap::<String, usize>::new();
+        // "objects" -> "objects[3]", the entry later headers inside an
+        // array of tables belong to
+        let mut current = HashMap::<String, String>::new();
+        let mut top_level = true;
+        for (n, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                top_level = false;
+            }
+            if top_level && !line.starts_with('#') {
+                // keys before the first header, e.g. "background = ..."
+                if let Some((key, _)) = line.split_once('=') {
+                    headers.insert(String::from(key.trim()), n + 1);
+                }
+            } else if let Some(name) = line.strip_prefix("[[").and_then(|l| l.strip_suffix("]]")) {
+                // [[objects.motion]] is counted per object
+                let name = name.trim();
+                let path = match name.rsplit_once('.') {
+                    Some((parent, last)) => format!("{}.{}", resolve(&current, parent), last),
+                    None => String::from(name),
+                };
+                let index = counters.entry(path.clone()).or_insert(0);
+                let path = format!("{}[{}]", path, index);
+                *index += 1;
+                headers.insert(path.clone(), n + 1);
+                let inner = format!("{}.", name);
+                current.retain(|key, _| !key.starts_with(&inner));
+                current.insert(String::from(name), path);
+            } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let name = name.trim().replace('"', "");
+                headers.insert(resolve(&current, &name), n + 1);
+            }
+        }
+        SourceLines { headers }
+    }
+
+    pub fn error(&self, at: &str, msg: String) -> SceneError {
+        SceneError {
+            file: None,
+            line: self.line_of(at),
+            msg: format!("{}: {}", at, msg),
+        }
+    }
+
+    // "objects[3].boundary" falls back to the line of "objects[3]"
+    pub fn line_of(&self, at: &str) -> Option<usize> {
+        let mut key = at;
+        loop {
+            if let Some(line) = self.headers.get(key) {
+                return Some(*line);
+            }
+            key = &key[..key.rfind('.')?];
+        }
+    }
+}
+
+// the header name with every array of tables in it replaced by its entry,
+// "objects.motion" in the fourth object becomes "objects[3].motion"
+fn resolve(current: &HashMap<String, String>, name: &str) -> String {
+    let mut path = String::new();
+    let mut end = 0;
+    for part in name.split('.') {
+        end += part.len();
+        path = match current.get(&name[..end]) {
+            Some(entry) => entry.clone(),
+            None if path.is_empty() => String::from(part),
+            None => format!("{}.{}", path, part),
+        };
+        end += 1;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOVING_SPHERES: &str = r#"
+[camera]
+lookfrom = [0.0, 0.0, 5.0]
+lookat = [0.0, 0.0, 0.0]
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = { type = "lambertian", albedo = [0.5, 0.5, 0.5] }
+
+[[objects.motion]]
+time = 0.0
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = { type = "lambertian", albedo = [0.5, 0.5, 0.5] }
+
+[[objects.motion]]
+time = 0.0
+
+[[objects.motion]]
+time = 1.0
+scale = [0.0, 1.0, 1.0]
+"#;
+
+    fn error_of(src: &str) -> SceneError {
+        match parse_scene(src, &RenderParam::default()) {
+            Ok(_) => panic!("the scene loaded"),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn motion_keys_are_counted_per_object() {
+        assert!(parse_scene(
+            &MOVING_SPHERES.replace("0.0, 1.0, 1.0", "2.0, 1.0, 1.0"),
+            &RenderParam::default()
+        )
+        .is_ok());
+        // the header of the second object's second key
+        let err = error_of(MOVING_SPHERES);
+        assert_eq!(err.line, Some(24));
+        assert!(err.msg.starts_with("objects[1].motion[1]:"), "{}", err.msg);
+    }
+
+    #[test]
+    fn unknown_object_keys_are_reported() {
+        let err = error_of(&MOVING_SPHERES.replacen(
+            "radius = 1.0",
+            "radius = 1.0\ntranslat = [5, 0, 0]",
+            2,
+        ));
+        assert_eq!(err.msg, "objects[0].translat: unknown key");
+    }
+}
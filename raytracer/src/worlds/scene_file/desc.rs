@@ -0,0 +1,421 @@
+// plain data mirroring a scene file, see scenes/cornell_box.toml for an example
+
+use crate::hittable::bvh::BvhStrategy;
+use crate::util::spectrum::Dispersion;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDesc {
+    #[serde(default = "default_background")]
+    pub background: TextureRef,
+    pub camera: CameraDesc,
+    // how every bvh in the scene is built, obj meshes included
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bvh: Option<BvhStrategy>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub textures: BTreeMap<String, TextureDesc>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub materials: BTreeMap<String, MaterialDesc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub objects: Vec<ObjectDesc>,
+    // only the geometry matters here, it is used to sample directions towards the lights
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lights: Vec<ObjectDesc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct CameraDesc {
+    #[serde(default = "default_vfov")]
+    pub vfov: f64,
+    pub lookfrom: [f64; 3],
+    pub lookat: [f64; 3],
+    #[serde(default = "default_vup")]
+    pub vup: [f64; 3],
+    #[serde(default)]
+    pub defocus_angle: f64,
+    #[serde(default = "default_focus_dist")]
+    pub focus_dist: f64,
+    #[serde(default)]
+    pub edge_detect: bool,
+    // the times rays are sent at, for motion blur
+    #[serde(default)]
+    pub shutter_open: f64,
+    #[serde(default = "default_shutter_close")]
+    pub shutter_close: f64,
+}
+
+// a color, the name of an entry in [textures], or an inline texture table
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum TextureRef {
+    Color([f64; 3]),
+    Named(String),
+    Inline(Box<TextureDesc>),
+}
+
+// solid colors are written as plain arrays
+impl From<TextureDesc> for TextureRef {
+    fn from(desc: TextureDesc) -> Self {
+        match desc {
+            TextureDesc::Solid { color } => TextureRef::Color(color),
+            desc => TextureRef::Inline(Box::new(desc)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TextureDesc {
+    Solid {
+        color: [f64; 3],
+    },
+    Checker {
+        scale: f64,
+        even: TextureRef,
+        odd: TextureRef,
+    },
+    Image {
+        path: String,
+    },
+    Noise {
+        scale: f64,
+        #[serde(default)]
+        turbulence: bool,
+        #[serde(default = "default_turb_depth")]
+        depth: i32,
+    },
+}
+
+// the name of an entry in [materials], or an inline material table
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum MaterialRef {
+    Named(String),
+    Inline(Box<MaterialDesc>),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MaterialDesc {
+    Lambertian {
+        albedo: TextureRef,
+    },
+    Metal {
+        albedo: TextureRef,
+        #[serde(default)]
+        fuzz: f64,
+    },
+    Dielectric {
+        refraction_index: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        roughness: Option<f64>,
+        #[serde(default, skip_serializing_if = "is_false")]
+        thin: bool,
+        // what light comes out as after tint_distance (default 1) inside
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tint: Option<[f64; 3]>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tint_distance: Option<f64>,
+        // seen with --spectral only
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dispersion: Option<Dispersion>,
+    },
+    DiffuseLight {
+        #[serde(default = "default_emit")]
+        emit: TextureRef,
+        // scales emit, so the color can stay a color
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        intensity: Option<f64>,
+        #[serde(default, skip_serializing_if = "is_false")]
+        two_sided: bool,
+        // kelvin, tints emit by the color of a black body
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        temperature: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        profile: Option<ProfileDesc>,
+    },
+    Isotropic {
+        albedo: [f64; 3],
+    },
+    // GGX metallic-roughness
+    Microfacet {
+        base_color: TextureRef,
+        #[serde(default = "scalar_zero")]
+        metallic: ScalarRef,
+        #[serde(default = "scalar_half")]
+        roughness: ScalarRef,
+    },
+    // Burley's principled BSDF, unset inputs take Blender's defaults
+    Principled {
+        base_color: TextureRef,
+        #[serde(default = "scalar_zero")]
+        metallic: ScalarRef,
+        #[serde(default = "scalar_half")]
+        roughness: ScalarRef,
+        #[serde(default = "scalar_half")]
+        specular: ScalarRef,
+        #[serde(default = "scalar_zero")]
+        specular_tint: ScalarRef,
+        #[serde(default = "scalar_zero")]
+        sheen: ScalarRef,
+        #[serde(default = "scalar_half")]
+        sheen_tint: ScalarRef,
+        #[serde(default = "scalar_zero")]
+        clearcoat: ScalarRef,
+        #[serde(default = "scalar_one")]
+        clearcoat_gloss: ScalarRef,
+        #[serde(default = "scalar_zero")]
+        transmission: ScalarRef,
+        #[serde(default = "scalar_zero")]
+        subsurface: ScalarRef,
+        #[serde(default = "default_ior")]
+        ior: f64,
+    },
+}
+
+// how an emitter's light changes with the angle to its normal
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ProfileDesc {
+    // cos^exponent, nothing past cone degrees
+    Spot {
+        #[serde(default = "default_exponent")]
+        exponent: f64,
+        #[serde(default = "default_cone")]
+        cone: f64,
+    },
+    // values at angles in degrees from the normal
+    Table {
+        angles: Vec<f64>,
+        values: Vec<f64>,
+    },
+    // an IES LM-63 file
+    Ies {
+        file: String,
+    },
+}
+
+// a number, or a texture read from its first channel
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum ScalarRef {
+    Value(f64),
+    Texture(TextureRef),
+}
+
+impl ScalarRef {
+    // solid gray textures are written as plain numbers
+    pub fn from_texture(desc: TextureDesc) -> ScalarRef {
+        match desc {
+            TextureDesc::Solid { color } if color[0] == color[1] && color[1] == color[2] => {
+                ScalarRef::Value(color[0])
+            }
+            desc => ScalarRef::Texture(desc.into()),
+        }
+    }
+}
+
+// every object may be rotated around y, then translated (same order as the worlds/ builders),
+// then go through any matrix, then move along its keyframes
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ObjectDesc {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotate_y: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translate: Option<[f64; 3]>,
+    // an affine transform, column by column like glTF
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<[f64; 16]>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub motion: Vec<KeyframeDesc>,
+    // used for every hit instead of the materials of the shape, like an Instance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material_override: Option<MaterialRef>,
+    #[serde(flatten)]
+    pub shape: ShapeDesc,
+    // every key not read above, the shape's own included; a flattened
+    // shape cannot deny unknown fields, so the loader checks these instead
+    #[serde(flatten, skip_serializing)]
+    pub extra: toml::value::Table,
+}
+
+// a pose of a moving object: scaled, then rotated, then moved
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct KeyframeDesc {
+    pub time: f64,
+    #[serde(default)]
+    pub translate: [f64; 3],
+    // unit quaternion x, y, z, w
+    #[serde(default = "default_quaternion")]
+    pub rotate: [f64; 4],
+    #[serde(default = "default_scale")]
+    pub scale: [f64; 3],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShapeDesc {
+    Sphere {
+        center: [f64; 3],
+        radius: f64,
+        // moving sphere, from center at time 0 to center2 at time 1
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        center2: Option<[f64; 3]>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<MaterialRef>,
+    },
+    Quad {
+        q: [f64; 3],
+        u: [f64; 3],
+        v: [f64; 3],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normal_map: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<MaterialRef>,
+    },
+    Cube {
+        a: [f64; 3],
+        b: [f64; 3],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normal_map: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<MaterialRef>,
+    },
+    // materials come from the .mtl file
+    Obj {
+        file: String,
+        #[serde(default = "default_zoom")]
+        zoom: f64,
+        #[serde(default)]
+        offset: [f64; 3],
+        #[serde(default)]
+        rot_x: f64,
+        #[serde(default)]
+        rot_y: f64,
+        #[serde(default)]
+        rot_z: f64,
+        // for files without normals
+        #[serde(default)]
+        flat_normals: bool,
+    },
+    // a .ply or .stl, its vertex colors if it has them, else gray
+    Mesh {
+        file: String,
+        #[serde(default = "default_zoom")]
+        zoom: f64,
+        #[serde(default)]
+        offset: [f64; 3],
+        #[serde(default)]
+        rot_x: f64,
+        #[serde(default)]
+        rot_y: f64,
+        #[serde(default)]
+        rot_z: f64,
+        #[serde(default)]
+        flat_normals: bool,
+    },
+    ConstantMedium {
+        density: f64,
+        albedo: [f64; 3],
+        boundary: Box<ObjectDesc>,
+    },
+    Bvh {
+        objects: Vec<ObjectDesc>,
+    },
+    List {
+        objects: Vec<ObjectDesc>,
+    },
+}
+
+impl ShapeDesc {
+    // the keys each shape reads besides "type", anything else in its
+    // object table ends up in ObjectDesc::extra
+    pub fn keys(&self) -> &'static [&'static str] {
+        match self {
+            ShapeDesc::Sphere { .. } => &["center", "radius", "center2", "material"],
+            ShapeDesc::Quad { .. } => &["q", "u", "v", "normal_map", "material"],
+            ShapeDesc::Cube { .. } => &["a", "b", "normal_map", "material"],
+            ShapeDesc::Obj { .. } | ShapeDesc::Mesh { .. } => &[
+                "file",
+                "zoom",
+                "offset",
+                "rot_x",
+                "rot_y",
+                "rot_z",
+                "flat_normals",
+            ],
+            ShapeDesc::ConstantMedium { .. } => &["density", "albedo", "boundary"],
+            ShapeDesc::Bvh { .. } | ShapeDesc::List { .. } => &["objects"],
+        }
+    }
+}
+
+impl From<ShapeDesc> for ObjectDesc {
+    fn from(shape: ShapeDesc) -> Self {
+        ObjectDesc {
+            rotate_y: None,
+            translate: None,
+            matrix: None,
+            motion: Vec::new(),
+            material_override: None,
+            shape,
+            extra: toml::value::Table::new(),
+        }
+    }
+}
+
+pub fn default_background() -> TextureRef {
+    TextureRef::Color([0.5, 0.7, 1.0])
+}
+fn default_vfov() -> f64 {
+    90.0
+}
+fn default_vup() -> [f64; 3] {
+    [0.0, 1.0, 0.0]
+}
+fn default_quaternion() -> [f64; 4] {
+    [0.0, 0.0, 0.0, 1.0]
+}
+fn default_scale() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+fn default_focus_dist() -> f64 {
+    10.0
+}
+fn default_shutter_close() -> f64 {
+    1.0
+}
+fn default_emit() -> TextureRef {
+    TextureRef::Color([1.0, 1.0, 1.0])
+}
+fn default_exponent() -> f64 {
+    1.0
+}
+fn default_cone() -> f64 {
+    90.0
+}
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+fn scalar_zero() -> ScalarRef {
+    ScalarRef::Value(0.0)
+}
+fn scalar_half() -> ScalarRef {
+    ScalarRef::Value(0.5)
+}
+fn scalar_one() -> ScalarRef {
+    ScalarRef::Value(1.0)
+}
+fn default_ior() -> f64 {
+    1.5
+}
+fn default_turb_depth() -> i32 {
+    7
+}
+fn default_zoom() -> f64 {
+    1.0
+}
@@ -0,0 +1,202 @@
+// write a built scene back into the scene file format, e.g. to freeze the
+// random placement of final_scene into a reproducible file
+
+use crate::camera::background::BackGround;
+use crate::camera::Camera;
+use crate::hittable::hittable_list::HittableList;
+use crate::worlds::scene_file::desc::{
+    CameraDesc, MaterialDesc, MaterialRef, ObjectDesc, SceneDesc, ShapeDesc,
+};
+use crate::worlds::scene_file::SceneError;
+use std::collections::{BTreeMap, HashMap};
+use toml::Value;
+
+pub fn describe_scene(
+    world: &HittableList,
+    lights: &HittableList,
+    cam: &Camera,
+    back_ground: &BackGround,
+) -> Result<SceneDesc, SceneError> {
+    let background = back_ground
+        .tex
+        .describe()
+        .ok_or_else(|| export_error("background", "texture cannot be described"))?
+        .into();
+    let mut objects = describe_list(world, "objects")?;
+    let mut lights = describe_list(lights, "lights")?;
+
+    // shared materials go to [materials] once instead of being repeated
+    // inline; all of them are numbered first, so every name can be padded
+    // to the same width and the table sorts in the order they were met
+    let mut materials = MaterialNames::default();
+    // the lights list only needs geometry
+    for (list, drop) in [(&mut objects, false), (&mut lights, true)] {
+        for object in list.iter_mut() {
+            visit(object, drop, &mut |material| {
+                if let Some(MaterialRef::Inline(desc)) = material {
+                    materials.id(desc);
+                }
+            });
+        }
+    }
+    for object in objects.iter_mut() {
+        visit(object, false, &mut |material| {
+            if let Some(MaterialRef::Inline(desc)) = material {
+                let id = materials.id(desc);
+                *material = Some(MaterialRef::Named(materials.name(id)));
+            }
+        });
+    }
+
+    Ok(SceneDesc {
+        background,
+        camera: CameraDesc {
+            vfov: cam.vfov,
+            lookfrom: cam.lookfrom.e,
+            lookat: cam.lookat.e,
+            vup: cam.vup.e,
+            defocus_angle: cam.defocus_angle,
+            focus_dist: cam.focus_dist,
+            edge_detect: cam.edge_detect,
+            shutter_open: cam.shutter_open,
+            shutter_close: cam.shutter_close,
+        },
+        bvh: None,
+        textures: BTreeMap::new(),
+        materials: materials.table(),
+        objects,
+        lights,
+    })
+}
+
+#[derive(Default)]
+struct MaterialNames {
+    descs: Vec<MaterialDesc>,
+    ids: HashMap<String, usize>, // serialized material -> its number
+}
+
+impl MaterialNames {
+    fn id(&mut self, desc: &MaterialDesc) -> usize {
+        // f64 fields rule out hashing the desc itself
+        let key = toml::to_string(desc).unwrap_or_else(|_| format!("{:?}", desc));
+        *self.ids.entry(key).or_insert_with(|| {
+            self.descs.push(desc.clone());
+            self.descs.len() - 1
+        })
+    }
+
+    fn name(&self, id: usize) -> String {
+        let width = (self.descs.len() - 1).to_string().len();
+        format!("material_{:0width$}", id, width = width)
+    }
+
+    fn table(&self) -> BTreeMap<String, MaterialDesc> {
+        let named = self.descs.iter().enumerate();
+        named
+            .map(|(id, desc)| (self.name(id), desc.clone()))
+            .collect()
+    }
+}
+
+// calls `f` on every material slot of the object and what it holds; with
+// `drop` they are emptied instead
+fn visit(object: &mut ObjectDesc, drop: bool, f: &mut impl FnMut(&mut Option<MaterialRef>)) {
+    if drop {
+        object.material_override = None;
+    } else {
+        f(&mut object.material_override);
+    }
+    match &mut object.shape {
+        ShapeDesc::Sphere { material, .. }
+        | ShapeDesc::Quad { material, .. }
+        | ShapeDesc::Cube { material, .. } => {
+            if drop {
+                *material = None;
+            } else {
+                f(material);
+            }
+        }
+        // the boundary's material is never used
+        ShapeDesc::ConstantMedium { boundary, .. } => visit(boundary, true, f),
+        ShapeDesc::Bvh { objects } | ShapeDesc::List { objects } => {
+            for object in objects.iter_mut() {
+                visit(object, drop, f);
+            }
+        }
+        ShapeDesc::Obj { .. } | ShapeDesc::Mesh { .. } => {}
+    }
+}
+
+pub fn export_scene(
+    world: &HittableList,
+    lights: &HittableList,
+    cam: &Camera,
+    back_ground: &BackGround,
+) -> Result<String, SceneError> {
+    let desc = describe_scene(world, lights, cam, back_ground)?;
+    let to_err = |err: toml::ser::Error| export_error("scene", &err.to_string());
+
+    // going through a Value puts plain values before sub-tables, as TOML
+    // requires, but it would also put [[objects]] before [camera]; so the
+    // object lists are split off and written last
+    let Value::Table(mut head) = Value::try_from(&desc).map_err(to_err)? else {
+        unreachable!("a scene serializes to a table");
+    };
+    let mut tail = toml::map::Map::new();
+    for key in ["objects", "lights"] {
+        if let Some(list) = head.remove(key) {
+            tail.insert(String::from(key), list);
+        }
+    }
+    let head = toml::to_string(&Value::Table(head)).map_err(to_err)?;
+    let tail = toml::to_string(&Value::Table(tail)).map_err(to_err)?;
+    Ok(format!("{}\n{}", head, tail))
+}
+
+fn describe_list(list: &HittableList, at: &str) -> Result<Vec<ObjectDesc>, SceneError> {
+    list.objects
+        .iter()
+        .enumerate()
+        .map(|(i, object)| {
+            object.describe().ok_or_else(|| {
+                export_error(&format!("{}[{}]", at, i), "object cannot be described")
+            })
+        })
+        .collect()
+}
+
+fn export_error(at: &str, msg: &str) -> SceneError {
+    SceneError {
+        file: None,
+        line: None,
+        msg: format!("{}: {}", at, msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::RenderParam;
+    use crate::worlds::scene_file::parse_scene;
+    use crate::worlds::SCENES;
+    use rayon::prelude::*;
+
+    #[test]
+    fn every_scene_loads_back() {
+        let param = RenderParam {
+            image_width: 8,
+            samples_per_pixel: 1,
+            ..RenderParam::default()
+        };
+        // the crowd reads its obj once per instance when loaded back, so the
+        // scenes go side by side
+        SCENES.par_iter().for_each(|(name, builder)| {
+            let (world, lights, cam, back_ground) = builder(&param);
+            let src = export_scene(&world, &lights, &cam, &back_ground)
+                .unwrap_or_else(|err| panic!("{}: {}", name, err));
+            if let Err(err) = parse_scene(&src, &param) {
+                panic!("{} does not load back: {}", name, err);
+            }
+        });
+    }
+}
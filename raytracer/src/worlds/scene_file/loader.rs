@@ -0,0 +1,613 @@
+// turn a SceneDesc into the hittables, camera and background the renderer uses
+
+use crate::camera::background::BackGround;
+use crate::camera::Camera;
+use crate::features::mesh_file::mesh_file;
+use crate::features::normal_map::{MapMap, NormalMap, OriginMap};
+use crate::features::obj_mesh::{obj_mesh, LoadParam};
+use crate::hittable::bvh::{build_bvh, BvhLayout, BvhStrategy};
+use crate::hittable::hittable_list::HittableList;
+use crate::hittable::instances::constant_medium::ConstMedium;
+use crate::hittable::instances::flats::{cube, Quad};
+use crate::hittable::instances::sphere::Sphere;
+use crate::hittable::transforms::instance::Instance;
+use crate::hittable::transforms::motion::{Keyframe, Motion};
+use crate::hittable::transforms::rotate_y::RotateY;
+use crate::hittable::transforms::transform::Transform;
+use crate::hittable::transforms::translate::Translate;
+use crate::hittable::Hittable;
+use crate::materials::dielectric::Dielectric;
+use crate::materials::diffuse_light::profile::{AngularTable, EmissionProfile};
+use crate::materials::diffuse_light::DiffuseLight;
+use crate::materials::isotropic::Isotropic;
+use crate::materials::lambertian::Lambertian;
+use crate::materials::metal::Metal;
+use crate::materials::microfacet::Microfacet;
+use crate::materials::principled::{Principled, PrincipledParam};
+use crate::materials::Material;
+use crate::textures::checker_texture::CheckerTexture;
+use crate::textures::image_texture::ImageTexture;
+use crate::textures::noise_texture::NoiseTexture;
+use crate::textures::{SolidColor, Texture};
+use crate::util::color::Color;
+use crate::util::image_process::locate_image;
+use crate::util::mat4::Mat4;
+use crate::util::spectrum::D_LINE;
+use crate::util::vec3::{cross, Vec3};
+use crate::util::RenderParam;
+use crate::worlds::scene_file::desc::*;
+use crate::worlds::scene_file::{SceneError, SourceLines};
+use crate::worlds::Scene;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+pub struct Loader {
+    lines: SourceLines,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    normal_maps: HashMap<String, Arc<dyn NormalMap>>,
+    resolving: Vec<String>, // named textures being built, to catch cycles
+    bvh: BvhStrategy,
+    bvh_layout: BvhLayout,
+}
+
+impl Loader {
+    pub fn new(lines: SourceLines) -> Self {
+        Loader {
+            lines,
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+            normal_maps: HashMap::new(),
+            resolving: Vec::new(),
+            bvh: BvhStrategy::default(),
+            bvh_layout: BvhLayout::default(),
+        }
+    }
+
+    pub fn scene(&mut self, desc: &SceneDesc, param: &RenderParam) -> Result<Scene, SceneError> {
+        // the command line wins over the file
+        self.bvh = param.bvh.or(desc.bvh).unwrap_or_default();
+        self.bvh_layout = param.bvh_layout;
+        let mut world = HittableList::default();
+        for (i, object) in desc.objects.iter().enumerate() {
+            world.add(self.object(desc, object, &format!("objects[{}]", i), false)?);
+        }
+        let mut lights = HittableList::default();
+        for (i, object) in desc.lights.iter().enumerate() {
+            lights.add(self.object(desc, object, &format!("lights[{}]", i), true)?);
+        }
+
+        let cam = self.camera(&desc.camera, param)?;
+        let back_ground = BackGround::new(self.texture(desc, &desc.background, "background")?);
+
+        Ok((world, lights, cam, back_ground))
+    }
+
+    fn error(&self, at: &str, msg: String) -> SceneError {
+        self.lines.error(at, msg)
+    }
+
+    fn camera(&self, desc: &CameraDesc, param: &RenderParam) -> Result<Camera, SceneError> {
+        if desc.vfov <= 0.0 || desc.vfov >= 180.0 {
+            return Err(self.error("camera", format!("vfov {} is not in (0, 180)", desc.vfov)));
+        }
+        if desc.lookfrom == desc.lookat {
+            return Err(self.error("camera", String::from("lookfrom and lookat are the same")));
+        }
+        if desc.shutter_close < desc.shutter_open {
+            return Err(self.error(
+                "camera",
+                format!(
+                    "shutter closes at {} before it opens at {}",
+                    desc.shutter_close, desc.shutter_open
+                ),
+            ));
+        }
+        let mut cam = Camera::default();
+
+        cam.aspect_ratio = param.aspect_ratio;
+        cam.image_width = param.image_width;
+        cam.samples_per_pixel = param.samples_per_pixel;
+        cam.max_recurse_depth = param.max_recurse_depth;
+
+        cam.vfov = desc.vfov;
+        cam.lookfrom = vec3(desc.lookfrom);
+        cam.lookat = vec3(desc.lookat);
+        cam.vup = vec3(desc.vup);
+
+        cam.defocus_angle = desc.defocus_angle;
+        cam.focus_dist = desc.focus_dist;
+
+        cam.shutter_open = desc.shutter_open;
+        cam.shutter_close = desc.shutter_close;
+
+        cam.edge_detect = desc.edge_detect;
+
+        cam.initialize();
+        Ok(cam)
+    }
+
+    fn texture(
+        &mut self,
+        scene: &SceneDesc,
+        tex: &TextureRef,
+        at: &str,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        match tex {
+            TextureRef::Color(c) => Ok(Arc::new(SolidColor::new_color(vec3(*c)))),
+            TextureRef::Inline(desc) => self.texture_desc(scene, desc, at),
+            TextureRef::Named(name) => {
+                if let Some(tex) = self.textures.get(name) {
+                    return Ok(tex.clone());
+                }
+                let Some(desc) = scene.textures.get(name) else {
+                    return Err(self.error(at, format!("unknown texture '{}'", name)));
+                };
+                if self.resolving.contains(name) {
+                    return Err(self.error(at, format!("texture '{}' refers to itself", name)));
+                }
+                self.resolving.push(name.clone());
+                let tex = self.texture_desc(scene, desc, &format!("textures.{}", name));
+                self.resolving.pop();
+                let tex = tex?;
+                self.textures.insert(name.clone(), tex.clone());
+                Ok(tex)
+            }
+        }
+    }
+
+    fn texture_desc(
+        &mut self,
+        scene: &SceneDesc,
+        desc: &TextureDesc,
+        at: &str,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        Ok(match desc {
+            TextureDesc::Solid { color } => Arc::new(SolidColor::new_color(vec3(*color))),
+            TextureDesc::Checker { scale, even, odd } => {
+                if *scale <= 0.0 {
+                    return Err(self.error(at, String::from("checker scale must be positive")));
+                }
+                let even = self.texture(scene, even, at)?;
+                let odd = self.texture(scene, odd, at)?;
+                Arc::new(CheckerTexture::_new_tex(*scale, even, odd))
+            }
+            TextureDesc::Image { path } => {
+                if locate_image(path).is_none() {
+                    return Err(self.error(at, format!("cannot find image '{}'", path)));
+                }
+                Arc::new(ImageTexture::new_path(path))
+            }
+            TextureDesc::Noise {
+                scale,
+                turbulence,
+                depth,
+            } => Arc::new(NoiseTexture::_new(*scale, *turbulence, *depth)),
+        })
+    }
+
+    fn material(
+        &mut self,
+        scene: &SceneDesc,
+        mat: Option<&MaterialRef>,
+        at: &str,
+        material_optional: bool,
+    ) -> Result<Arc<dyn Material>, SceneError> {
+        match mat {
+            // lights and medium boundaries only need a placeholder, like in the worlds/ builders
+            None if material_optional => Ok(Arc::new(Lambertian::<SolidColor>::new_color(
+                Color::default(),
+            ))),
+            None => Err(self.error(at, String::from("missing material"))),
+            Some(MaterialRef::Inline(desc)) => self.material_desc(scene, desc, at),
+            Some(MaterialRef::Named(name)) => {
+                if let Some(mat) = self.materials.get(name) {
+                    return Ok(mat.clone());
+                }
+                let Some(desc) = scene.materials.get(name) else {
+                    return Err(self.error(at, format!("unknown material '{}'", name)));
+                };
+                let mat = self.material_desc(scene, desc, &format!("materials.{}", name))?;
+                self.materials.insert(name.clone(), mat.clone());
+                Ok(mat)
+            }
+        }
+    }
+
+    fn material_desc(
+        &mut self,
+        scene: &SceneDesc,
+        desc: &MaterialDesc,
+        at: &str,
+    ) -> Result<Arc<dyn Material>, SceneError> {
+        Ok(match desc {
+            MaterialDesc::Lambertian { albedo } => {
+                Arc::new(Lambertian::new_tex(self.texture(scene, albedo, at)?))
+            }
+            MaterialDesc::Metal { albedo, fuzz } => {
+                Arc::new(Metal::new_tex(self.texture(scene, albedo, at)?, *fuzz))
+            }
+            MaterialDesc::Dielectric {
+                refraction_index,
+                roughness,
+                thin,
+                tint,
+                tint_distance,
+                dispersion,
+            } => {
+                if *refraction_index <= 0.0 {
+                    return Err(self.error(at, String::from("refraction_index must be positive")));
+                }
+                let mut glass = Dielectric::new(*refraction_index);
+                if let Some(roughness) = roughness {
+                    if !(0.0..=1.0).contains(roughness) {
+                        return Err(self.error(at, String::from("roughness must be in [0, 1]")));
+                    }
+                    glass = glass.with_roughness(*roughness);
+                }
+                if *thin {
+                    if roughness.is_some() || tint.is_some() {
+                        return Err(self.error(
+                            at,
+                            String::from("thin glass is smooth and clear, drop roughness and tint"),
+                        ));
+                    }
+                    glass = glass.thin();
+                }
+                if let Some(tint) = tint {
+                    let distance = tint_distance.unwrap_or(1.0);
+                    if distance <= 0.0 {
+                        return Err(self.error(at, String::from("tint_distance must be positive")));
+                    }
+                    glass = glass.with_tint(vec3(*tint), distance);
+                } else if tint_distance.is_some() {
+                    return Err(self.error(at, String::from("tint_distance without a tint")));
+                }
+                if let Some(dispersion) = dispersion {
+                    let n = dispersion.ior(D_LINE);
+                    if !n.is_finite() || n <= 0.0 {
+                        return Err(
+                            self.error(at, String::from("dispersion gives no index at the d line"))
+                        );
+                    }
+                    glass = glass.with_dispersion(*dispersion);
+                }
+                Arc::new(glass)
+            }
+            MaterialDesc::DiffuseLight {
+                emit,
+                intensity,
+                two_sided,
+                temperature,
+                profile,
+            } => {
+                let mut light = DiffuseLight::new_tex(self.texture(scene, emit, at)?);
+                if let Some(intensity) = intensity {
+                    if *intensity < 0.0 {
+                        return Err(self.error(at, String::from("intensity cannot be negative")));
+                    }
+                    light = light.with_intensity(*intensity);
+                }
+                if *two_sided {
+                    light = light.two_sided();
+                }
+                if let Some(kelvin) = temperature {
+                    if *kelvin < 500.0 {
+                        return Err(
+                            self.error(at, String::from("temperature is in kelvin, at least 500"))
+                        );
+                    }
+                    light = light.with_temperature(*kelvin);
+                }
+                if let Some(profile) = profile {
+                    light = light.with_profile(self.profile(profile, at)?);
+                }
+                Arc::new(light)
+            }
+            MaterialDesc::Isotropic { albedo } => Arc::new(Isotropic::new_color(vec3(*albedo))),
+            MaterialDesc::Microfacet {
+                base_color,
+                metallic,
+                roughness,
+            } => Arc::new(Microfacet::new_tex(
+                self.texture(scene, base_color, at)?,
+                self.scalar(scene, metallic, at)?,
+                self.scalar(scene, roughness, at)?,
+            )),
+            MaterialDesc::Principled {
+                base_color,
+                metallic,
+                roughness,
+                specular,
+                specular_tint,
+                sheen,
+                sheen_tint,
+                clearcoat,
+                clearcoat_gloss,
+                transmission,
+                subsurface,
+                ior,
+            } => {
+                if *ior <= 0.0 {
+                    return Err(self.error(at, String::from("ior must be positive")));
+                }
+                Arc::new(Principled::new(PrincipledParam {
+                    base_color: self.texture(scene, base_color, at)?,
+                    metallic: self.scalar(scene, metallic, at)?,
+                    roughness: self.scalar(scene, roughness, at)?,
+                    specular: self.scalar(scene, specular, at)?,
+                    specular_tint: self.scalar(scene, specular_tint, at)?,
+                    sheen: self.scalar(scene, sheen, at)?,
+                    sheen_tint: self.scalar(scene, sheen_tint, at)?,
+                    clearcoat: self.scalar(scene, clearcoat, at)?,
+                    clearcoat_gloss: self.scalar(scene, clearcoat_gloss, at)?,
+                    transmission: self.scalar(scene, transmission, at)?,
+                    subsurface: self.scalar(scene, subsurface, at)?,
+                    ior: *ior,
+                }))
+            }
+        })
+    }
+
+    fn profile(&self, desc: &ProfileDesc, at: &str) -> Result<EmissionProfile, SceneError> {
+        Ok(match desc {
+            ProfileDesc::Spot { exponent, cone } => {
+                if *exponent < 0.0 || !(0.0..=90.0).contains(cone) {
+                    return Err(self.error(
+                        at,
+                        String::from("a spot needs exponent >= 0 and cone in [0, 90] degrees"),
+                    ));
+                }
+                EmissionProfile::Spot {
+                    exponent: *exponent,
+                    cone: *cone,
+                }
+            }
+            ProfileDesc::Table { angles, values } => EmissionProfile::Table(
+                AngularTable::new(angles.clone(), values.clone())
+                    .map_err(|err| self.error(at, err))?,
+            ),
+            ProfileDesc::Ies { file } => {
+                let text = std::fs::read_to_string(file)
+                    .map_err(|err| self.error(at, format!("cannot read '{}': {}", file, err)))?;
+                EmissionProfile::Table(
+                    AngularTable::from_ies(&text)
+                        .map_err(|err| self.error(at, format!("'{}': {}", file, err)))?,
+                )
+            }
+        })
+    }
+
+    fn scalar(
+        &mut self,
+        scene: &SceneDesc,
+        value: &ScalarRef,
+        at: &str,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        match value {
+            ScalarRef::Value(x) => Ok(Arc::new(SolidColor::new_color(Color::new(*x, *x, *x)))),
+            ScalarRef::Texture(tex) => self.texture(scene, tex, at),
+        }
+    }
+
+    fn normal_map(
+        &mut self,
+        path: Option<&String>,
+        at: &str,
+    ) -> Result<Arc<dyn NormalMap>, SceneError> {
+        let Some(path) = path else {
+            return Ok(Arc::new(OriginMap::default()));
+        };
+        if let Some(nmap) = self.normal_maps.get(path) {
+            return Ok(nmap.clone());
+        }
+        if locate_image(path).is_none() {
+            return Err(self.error(at, format!("cannot find normal map '{}'", path)));
+        }
+        let nmap: Arc<dyn NormalMap> = Arc::new(MapMap::new(path));
+        self.normal_maps.insert(path.clone(), nmap.clone());
+        Ok(nmap)
+    }
+
+    fn object(
+        &mut self,
+        scene: &SceneDesc,
+        desc: &ObjectDesc,
+        at: &str,
+        material_optional: bool,
+    ) -> Result<Box<dyn Hittable>, SceneError> {
+        // the flattened shape leaves its own keys in extra too
+        let known = desc.shape.keys();
+        let mut keys = desc.extra.keys().filter(|key| *key != "type");
+        if let Some(key) = keys.find(|key| !known.contains(&key.as_str())) {
+            let at = format!("{}.{}", at, key);
+            return Err(self.error(&at, String::from("unknown key")));
+        }
+        let mut object: Box<dyn Hittable> = match &desc.shape {
+            ShapeDesc::Sphere {
+                center,
+                radius,
+                center2,
+                material,
+            } => {
+                if *radius == 0.0 {
+                    return Err(self.error(at, String::from("sphere radius is zero")));
+                }
+                let mat = self.material(scene, material.as_ref(), at, material_optional)?;
+                match center2 {
+                    Some(center2) => Box::new(Sphere::new_motive(
+                        vec3(*center),
+                        vec3(*center2),
+                        *radius,
+                        mat,
+                    )),
+                    None => Box::new(Sphere::new_static(vec3(*center), *radius, mat)),
+                }
+            }
+            ShapeDesc::Quad {
+                q,
+                u,
+                v,
+                normal_map,
+                material,
+            } => {
+                if cross(&vec3(*u), &vec3(*v)).near_zero() {
+                    return Err(self.error(at, String::from("quad edges u and v are parallel")));
+                }
+                let mat = self.material(scene, material.as_ref(), at, material_optional)?;
+                let nmap = self.normal_map(normal_map.as_ref(), at)?;
+                Box::new(Quad::new(vec3(*q), vec3(*u), vec3(*v), mat, nmap))
+            }
+            ShapeDesc::Cube {
+                a,
+                b,
+                normal_map,
+                material,
+            } => {
+                let mat = self.material(scene, material.as_ref(), at, material_optional)?;
+                let nmap = self.normal_map(normal_map.as_ref(), at)?;
+                Box::new(cube(vec3(*a), vec3(*b), mat, nmap))
+            }
+            ShapeDesc::Obj {
+                file,
+                zoom,
+                offset,
+                rot_x,
+                rot_y,
+                rot_z,
+                flat_normals,
+            } => {
+                if !Path::new(file).is_file() {
+                    return Err(self.error(at, format!("cannot find obj file '{}'", file)));
+                }
+                obj_mesh(LoadParam {
+                    file,
+                    zoom: *zoom,
+                    offset: vec3(*offset),
+                    rot_x: *rot_x,
+                    rot_y: *rot_y,
+                    rot_z: *rot_z,
+                    bvh: self.bvh,
+                    layout: self.bvh_layout,
+                    flat_normals: *flat_normals,
+                })
+                .map_err(|err| self.error(at, err.to_string()))?
+            }
+            ShapeDesc::Mesh {
+                file,
+                zoom,
+                offset,
+                rot_x,
+                rot_y,
+                rot_z,
+                flat_normals,
+            } => {
+                if !Path::new(file).is_file() {
+                    return Err(self.error(at, format!("cannot find mesh file '{}'", file)));
+                }
+                mesh_file(LoadParam {
+                    file,
+                    zoom: *zoom,
+                    offset: vec3(*offset),
+                    rot_x: *rot_x,
+                    rot_y: *rot_y,
+                    rot_z: *rot_z,
+                    bvh: self.bvh,
+                    layout: self.bvh_layout,
+                    flat_normals: *flat_normals,
+                })
+                .map_err(|err| self.error(at, err.to_string()))?
+            }
+            ShapeDesc::ConstantMedium {
+                density,
+                albedo,
+                boundary,
+            } => {
+                if *density <= 0.0 {
+                    return Err(self.error(at, String::from("density must be positive")));
+                }
+                let boundary = self.object(scene, boundary, &format!("{}.boundary", at), true)?;
+                Box::new(ConstMedium::new_color(boundary, *density, vec3(*albedo)))
+            }
+            ShapeDesc::Bvh { objects } => {
+                if objects.is_empty() {
+                    return Err(self.error(at, String::from("bvh without objects")));
+                }
+                let mut children = Vec::new();
+                for (i, object) in objects.iter().enumerate() {
+                    let at = format!("{}.objects[{}]", at, i);
+                    children.push(self.object(scene, object, &at, material_optional)?);
+                }
+                build_bvh(children, self.bvh, self.bvh_layout)
+            }
+            ShapeDesc::List { objects } => {
+                let mut list = HittableList::default();
+                for (i, object) in objects.iter().enumerate() {
+                    let at = format!("{}.objects[{}]", at, i);
+                    list.add(self.object(scene, object, &at, material_optional)?);
+                }
+                Box::new(list)
+            }
+        };
+
+        if let Some(angle) = desc.rotate_y {
+            object = Box::new(RotateY::new(object, angle));
+        }
+        if let Some(offset) = desc.translate {
+            object = Box::new(Translate::new(object, vec3(offset)));
+        }
+        let matrix = match desc.matrix {
+            Some(m) => Some(self.matrix(m, at)?),
+            None => None,
+        };
+        if let Some(material) = &desc.material_override {
+            let material = self.material(scene, Some(material), at, material_optional)?;
+            let m = matrix.unwrap_or(Mat4::IDENTITY);
+            object = Box::new(Instance::new(object.into(), m).with_material(material));
+        } else if let Some(m) = matrix {
+            object = Box::new(Transform::new(object, m));
+        }
+        if !desc.motion.is_empty() {
+            let mut keys = Vec::with_capacity(desc.motion.len());
+            for (i, key) in desc.motion.iter().enumerate() {
+                keys.push(self.keyframe(key, &format!("{}.motion[{}]", at, i))?);
+            }
+            object = Box::new(Motion::new(object, keys));
+        }
+        Ok(object)
+    }
+
+    fn matrix(&self, m: [f64; 16], at: &str) -> Result<Mat4, SceneError> {
+        if m.iter().any(|x| !x.is_finite()) {
+            return Err(self.error(at, String::from("matrix is not finite")));
+        }
+        // columns first, so the last row is every fourth number
+        if [m[3], m[7], m[11], m[15]] != [0.0, 0.0, 0.0, 1.0] {
+            return Err(self.error(at, String::from("matrix does not end in 0 0 0 1")));
+        }
+        let m = Mat4::from_cols(m);
+        if m.inverse().is_none() {
+            return Err(self.error(at, String::from("matrix is not invertible")));
+        }
+        Ok(m)
+    }
+
+    fn keyframe(&self, desc: &KeyframeDesc, at: &str) -> Result<Keyframe, SceneError> {
+        let len = desc.rotate.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if !(len > 0.0 && len.is_finite()) {
+            return Err(self.error(at, String::from("rotate is not a quaternion")));
+        }
+        if desc.scale.contains(&0.0) {
+            return Err(self.error(at, String::from("scale flattens the object")));
+        }
+        Ok(Keyframe::new(desc.time)
+            .translate(vec3(desc.translate))
+            .orientation(desc.rotate.map(|x| x / len))
+            .scale(vec3(desc.scale)))
+    }
+}
+
+fn vec3(v: [f64; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}